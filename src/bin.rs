@@ -1,6 +1,8 @@
-#![recursion_limit = "512"]
+#![recursion_limit = "1024"]
 use std::cell::RefCell;
+use std::rc::Rc;
 
+use chrono::NaiveDate;
 use lazy_static::lazy_static;
 use vgtk::ext::*;
 use vgtk::lib::gdk;
@@ -8,13 +10,23 @@ use vgtk::lib::gio::{ApplicationExt, ApplicationFlags};
 use vgtk::lib::gtk::*;
 use vgtk::{gtk, run, Component, UpdateAction};
 
-use vgtk_components::checkbox::CheckBox;
+use vgtk_components::checkbox::{CheckBox, CheckState};
+use vgtk_components::checkbox_group::{CheckBoxGroup, GroupOption};
 use vgtk_components::date::{Date, DateInput};
-use vgtk_components::dropdown::Dropdown;
-use vgtk_components::file_chooser::FileChooser;
+use vgtk_components::dropdown::{BlockingSource, Dropdown, ItemPage, ItemSource};
+use vgtk_components::file_chooser::{FileChooser, FileFilterSpec};
+use vgtk_components::form::{FieldValue, Form, FormField, FormValues};
 use vgtk_components::passwordbox::PasswordBox;
 use vgtk_components::phone_number::PhoneNumber;
 use vgtk_components::progress_bar::ProgressBar;
+use vgtk_components::progress_task::{ProgressHandle, ProgressTask};
+use vgtk_components::recent_files::RecentFiles;
+use vgtk_components::radio_group::RadioGroup;
+use vgtk_components::schema_form::SchemaForm;
+use vgtk_components::staged_progress::{Stage, StagedProgress};
+use vgtk_components::struct_form::{FormChoice, VgtkForm};
+use vgtk_components::suggestions::{StaticSuggestions, SuggestionSource};
+use vgtk_components::switch::Switch;
 use vgtk_components::textbox::TextBox;
 
 const STYLE: &str = "
@@ -38,21 +50,117 @@ lazy_static! {
         year: String::from("1990"),
     };
     static ref TEXTBOX_TEXT: String = String::from("Hello");
+    static ref GROUP_OPTIONS: Vec<GroupOption<String>> = vec![
+        GroupOption::new(String::from("alpha"), "Alpha"),
+        GroupOption::new(String::from("beta"), "Beta").with_description("The second option"),
+        GroupOption::new(String::from("delta"), "Delta"),
+    ];
+    static ref PLUGIN_SCHEMA: serde_json::Value = serde_json::json!({
+        "title": "Plugin settings",
+        "type": "object",
+        "required": ["endpoint"],
+        "properties": {
+            "endpoint": { "type": "string", "title": "Endpoint:" },
+            "token": { "type": "string", "format": "password", "title": "Token:" },
+            "since": { "type": "string", "format": "date", "title": "Since:" },
+            "level": { "enum": ["debug", "info", "warn"], "title": "Log level:", "default": "info" },
+            "retries": { "type": "integer", "title": "Retries:", "default": 3 },
+            "verbose": { "type": "boolean", "title": "Verbose" },
+            "proxy": {
+                "type": "object",
+                "title": "Proxy",
+                "properties": {
+                    "host": { "type": "string", "title": "Host:" },
+                    "port": { "type": "integer", "title": "Port:" }
+                }
+            },
+            "headers": {
+                "type": "array",
+                "title": "Headers",
+                "items": { "type": "string", "title": "Header:" }
+            }
+        }
+    });
+    static ref STAGES: Vec<Stage> = vec![Stage::new("Parse", 1.0), Stage::new("Validate", 1.0), Stage::new("Upload", 3.0)];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, FormChoice)]
+enum Theme {
+    Light,
+    Dark,
+    #[form(label = "Follow system")]
+    System,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::System
+    }
+}
+
+#[derive(Clone, Debug, VgtkForm)]
+struct Settings {
+    #[form(required, validate = "no_spaces", help = "Used to sign in, without spaces")]
+    user_name: String,
+    #[form(label = "Display name:")]
+    display_name: String,
+    #[form(order = 1)]
+    birthday: NaiveDate,
+    #[form(order = 1)]
+    theme: Theme,
+    #[form(switch, order = 2, label = "Send notifications")]
+    notifications: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            user_name: String::new(),
+            display_name: String::new(),
+            birthday: NaiveDate::from_ymd(1990, 1, 1),
+            theme: Theme::default(),
+            notifications: true,
+        }
+    }
+}
+
+fn no_spaces(value: &FieldValue) -> Result<(), String> {
+    match value {
+        FieldValue::Text(text) if text.contains(' ') => Err(String::from("Can't contain spaces")),
+        _ => Ok(()),
+    }
 }
 
 #[derive(Clone, Debug)]
 struct Model {
     progress_fraction: f64,
-    progress_text: RefCell<String>,
+    progress_pulsing: bool,
+    task: Option<ProgressTask>,
+    stage: usize,
+    stage_fraction: f64,
+    settings: Settings,
     text_buffer: RefCell<TextBuffer>,
+    file_source: Rc<dyn ItemSource>,
+    recipients: Rc<dyn SuggestionSource>,
 }
 
 impl Default for Model {
     fn default() -> Self {
         Self {
             progress_fraction: 0.0,
-            progress_text: RefCell::new(String::from("0%")),
+            progress_pulsing: false,
+            task: None,
+            stage: 0,
+            stage_fraction: 0.0,
+            settings: Settings::default(),
             text_buffer: RefCell::new(TextBuffer::new::<TextTagTable>(None)),
+            file_source: Rc::new(BlockingSource::new(list_files)),
+            recipients: Rc::new(StaticSuggestions::new(
+                ["alice@example.com", "bob@example.com", "carol@example.org"]
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+            )),
         }
     }
 }
@@ -64,6 +172,11 @@ enum Message {
     PrintBoolean { value: bool },
     PrintString { value: String },
     StartProgressBar,
+    SetPulsing { value: bool },
+    StartTask,
+    AdvanceStage,
+    Submit { values: FormValues },
+    SaveSettings { settings: Settings },
 }
 
 impl Component for Model {
@@ -99,13 +212,42 @@ impl Component for Model {
             Message::StartProgressBar => {
                 if self.progress_fraction + 0.1 <= 1.0 {
                     self.progress_fraction += 0.1;
-                    self.progress_text
-                        .replace(format!("{}%", (self.progress_fraction * 100.0).to_string()));
                     UpdateAction::Render
                 } else {
                     UpdateAction::None
                 }
             }
+            Message::SetPulsing { value } => {
+                self.progress_pulsing = value;
+                UpdateAction::Render
+            }
+            Message::StartTask => {
+                let (task, handle) = ProgressTask::new();
+                std::thread::spawn(move || import_files(handle));
+                self.task = Some(task);
+                UpdateAction::Render
+            }
+            Message::Submit { values } => {
+                let lines: Vec<_> = values.iter().map(|(key, value)| format!("{} = {:?}", key, value)).collect();
+                self.update(Message::PrintString { value: lines.join("\n") })
+            }
+            Message::SaveSettings { settings } => {
+                self.update(Message::PrintString { value: format!("{:?}", settings) });
+                self.settings = settings;
+                UpdateAction::Render
+            }
+            Message::AdvanceStage => {
+                if self.stage >= STAGES.len() {
+                    self.stage = 0;
+                    self.stage_fraction = 0.0;
+                } else if self.stage_fraction + 0.25 < 1.0 {
+                    self.stage_fraction += 0.25;
+                } else {
+                    self.stage += 1;
+                    self.stage_fraction = 0.0;
+                }
+                UpdateAction::Render
+            }
         }
     }
 
@@ -119,22 +261,66 @@ impl Component for Model {
                             <@Dropdown label=Some(String::from("Test Dropdown:")) items=DROPDOWN_LIST.clone() orientation=Orientation::Vertical />
                         </Box>
                         <@Dropdown label=Some(String::from("Test Dropdown:")) items=DROPDOWN_LIST.clone() />
-                        <@Dropdown items=DROPDOWN_LIST.clone() on_select=|value| Message::PrintString { value } />
+                        <@Dropdown items=DROPDOWN_LIST.clone() on_select=|value| Message::PrintString { value: value.unwrap_or_default() } />
+                        <@Dropdown label=Some(String::from("Required Dropdown:")) items=DROPDOWN_LIST.clone()
+                            placeholder=Some(String::from("— Select —")) clearable=true required=true
+                            on_validated=|value| Message::PrintBoolean { value } />
+                        <@Dropdown label=Some(String::from("Lazy Dropdown:")) source=Some(self.file_source.clone()) page_size=5
+                            placeholder=Some(String::from("— Select —")) on_select=|value| Message::PrintString { value: value.unwrap_or_default() } />
                         <@DateInput editable=true orientation=Orientation::Vertical spacing=20 />
                         <@DateInput label=Some(String::from("Date:")) editable=true min_year=2000 max_year=2100 />
                         <@DateInput label=Some(String::from("Date:")) full_width=true date=DATE.clone() editable=false />
-                        <@TextBox label=Some(String::from("Test Textbox:")) text=TEXTBOX_TEXT.clone() on_changed=|value| Message::PrintString { value } />
-                        <@TextBox text=TEXTBOX_TEXT.clone() on_changed=|value| Message::PrintString { value } />
-                        <@PasswordBox label=Some(String::from("Password")) full_width=true on_changed=|value| Message::PrintString { value } />
-                        <@PhoneNumber label=Some(String::from("Phone Number:")) full_width=true on_changed=|value| Message::PrintString { value } />
+                        <@TextBox label=Some(String::from("_Test Textbox:")) mnemonic=true text=TEXTBOX_TEXT.clone()
+                            help_text=Some(String::from("Alt+T focuses this box")) on_changed=|value| Message::PrintString { value } />
+                        <@TextBox label=Some(String::from("Search:")) debounce_ms=300
+                            on_changed=|value| Message::PrintString { value: format!("Search for {}", value) }
+                            on_commit=|value| Message::PrintString { value: format!("Committed {}", value) } />
+                        <@TextBox label=Some(String::from("To:")) suggestions=Some(self.recipients.clone()) suggestion_separator=Some(',')
+                            on_suggestion=|value| Message::PrintString { value: format!("Added {}", value) } />
+                        <@TextBox text=TEXTBOX_TEXT.clone() read_only=true on_changed=|value| Message::PrintString { value } />
+                        <@PasswordBox label=Some(String::from("Password")) full_width=true
+                            on_assessed=|assessment| Message::PrintString { value: format!("{:?}", assessment.strength) } />
+                        <@PhoneNumber label=Some(String::from("Phone Number:")) full_width=true tooltip=Some(String::from("US numbers only"))
+                            on_phone=|phone| Message::PrintString { value: phone.to_string() } />
                         <Box spacing=10>
-                            <@ProgressBar progress_text=self.progress_text.borrow().clone() progress_fraction=self.progress_fraction />
+                            <@ProgressBar text_format=Some(String::from("{percent}%")) show_text=true progress_fraction=self.progress_fraction
+                                pulsing=self.progress_pulsing />
                             <Button label="Start" on clicked=|_| Message::StartProgressBar />
+                            <@Switch label=Some(String::from("Pulse")) on_toggled=|value| Message::SetPulsing { value } />
                         </Box>
-                        <@CheckBox label=Some(String::from("Test Checkbox")) checked=true on_toggled=|value| Message::PrintBoolean { value } />
-                        <@CheckBox checked=false on_toggled=|value| Message::PrintBoolean { value } />
-                        <@FileChooser label=Some(String::from("Test Filechooser")) dialog_action=FileChooserAction::Open show_path=true />
-                        <@FileChooser label=Some(String::from("Test Filechooser")) dialog_action=FileChooserAction::SelectFolder show_path=true dialog_title="Select Folder" />
+                        <Box spacing=10>
+                            <@ProgressBar task=self.task.clone() show_text=true cancellable=true rate_unit=String::from("files")
+                                on_finished=|_| Message::PrintString { value: String::from("Import finished") } />
+                            <Button label="Import" on clicked=|_| Message::StartTask />
+                        </Box>
+                        <Box spacing=10>
+                            <@StagedProgress stages=STAGES.clone() current=self.stage stage_fraction=self.stage_fraction />
+                            <Button label="Advance" valign=Align::Start on clicked=|_| Message::AdvanceStage />
+                        </Box>
+                        <@CheckBox label=Some(String::from("Test Checkbox")) state=CheckState::Checked on_toggled=|state| Message::PrintBoolean { value: state.is_checked() } />
+                        <@CheckBox state=CheckState::Unchecked on_toggled=|state| Message::PrintBoolean { value: state.is_checked() } />
+                        <@CheckBox label=Some(String::from("Select All")) tri_state=true state=CheckState::Mixed
+                            on_toggled=|state| Message::PrintString { value: format!("{:?}", state) } />
+                        <@Switch label=Some(String::from("Test Switch")) on_toggled=|value| Message::PrintBoolean { value } />
+                        <@CheckBoxGroup label=Some(String::from("Test CheckBoxGroup:")) options=GROUP_OPTIONS.clone()
+                            orientation=Orientation::Horizontal on_changed=|values| Message::PrintString { value: values.join(", ") } />
+                        <@RadioGroup label=Some(String::from("Test RadioGroup:")) options=GROUP_OPTIONS.clone()
+                            selected=Some(String::from("alpha")) on_changed=|value| Message::PrintString { value } />
+                        <@FileChooser label=Some(String::from("Test Filechooser")) dialog_action=FileChooserAction::Open show_path=true
+                            filters=vec![FileFilterSpec::new("CSV files").pattern("*.csv").mime_type("text/csv")]
+                            recent_files=Some(RecentFiles::new("vgtk-components-demo", 5)) />
+                        <@FileChooser label=Some(String::from("Test Filechooser")) dialog_action=FileChooserAction::SelectFolder show_path=true dialog_title=String::from("Select Folder")
+                            editable_path=true />
+                        <@FileChooser label=Some(String::from("Test Multiple Files")) select_multiple=true show_path=true
+                            on_selected_multiple=|paths| Message::PrintString { value: format!("{:?}", paths) } />
+                        <@FileChooser label=Some(String::from("Test Save")) dialog_action=FileChooserAction::Save show_path=true
+                            suggested_name=Some(String::from("export.csv")) dialog_yes_text=String::from("_Save")
+                            filters=vec![FileFilterSpec::new("CSV files").pattern("*.csv")] />
+                        <@Form fields=signup_fields() submit_label=String::from("Sign up")
+                            on_submit=|values| Message::Submit { values } />
+                        <@SettingsForm value=self.settings.clone() on_submit=|settings| Message::SaveSettings { settings } />
+                        <@SchemaForm schema=PLUGIN_SCHEMA.clone() submit_label=Some(String::from("Apply"))
+                            on_submit=|value| Message::PrintString { value: value.to_string() } />
                         <Box Box::expand=true />
                     </Box>
                 </Window>
@@ -143,6 +329,55 @@ impl Component for Model {
     }
 }
 
+// Simulates a slow scan of the current directory
+fn list_files(offset: usize, limit: usize) -> Result<ItemPage, String> {
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    let mut files: Vec<String> = std::fs::read_dir(".")
+        .map_err(|error| format!("Couldn't list the files: {}", error))?
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    files.sort();
+
+    Ok(ItemPage {
+        has_more: offset + limit < files.len(),
+        items: files.into_iter().skip(offset).take(limit).collect(),
+    })
+}
+
+// Validators hold an `Rc`, so the fields can't live in a `lazy_static`
+fn signup_fields() -> Vec<FormField> {
+    vec![
+        FormField::text("name", "Name:").required(),
+        FormField::text("email", "Email:").required().placeholder("name@example.com").validate(|value| match value {
+            FieldValue::Text(text) if !text.contains('@') => Err(String::from("Enter a valid email address")),
+            _ => Ok(()),
+        }),
+        FormField::password("password", "Password:").required().help("At least 12 characters"),
+        FormField::date("birthday", "Birthday:"),
+        FormField::dropdown("plan", "Plan:", vec![String::from("Free"), String::from("Pro")]).required(),
+        FormField::checkbox("terms", "I accept the terms").required(),
+    ]
+}
+
+fn import_files(progress: ProgressHandle) {
+    // Doesn't know how many files there are at first
+    progress.set_text("Scanning…");
+    std::thread::sleep(std::time::Duration::from_secs(2));
+
+    let total = 50;
+    for done in 0..=total {
+        if progress.is_cancelled() {
+            progress.set_text("Cancelled");
+            break;
+        }
+        progress.set_progress(done, Some(total));
+        progress.set_text(&format!("{} of {} files", done, total));
+        std::thread::sleep(std::time::Duration::from_millis(200));
+    }
+    progress.finish();
+}
+
 fn main() {
     pretty_env_logger::init();
     std::process::exit(run::<Model>());
@@ -2,32 +2,104 @@ use vgtk::lib::gtk::prelude::*;
 use vgtk::lib::gtk::*;
 use vgtk::{gtk, gtk_if, Callback, Component, UpdateAction, VNode};
 
+use crate::labeled_field::help_view;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde_crate::Serialize, serde_crate::Deserialize), serde(crate = "serde_crate"))]
+pub enum CheckState {
+    Checked,
+    #[default]
+    Unchecked,
+    // Only rendered in tri-state mode, e.g. a "select all" header with some items selected
+    Mixed,
+}
+
+impl CheckState {
+    /// State reached when the user clicks the checkbox. Mixed can only be set by the parent,
+    /// clicking it checks the box.
+    pub fn toggled(self) -> Self {
+        match self {
+            CheckState::Unchecked | CheckState::Mixed => CheckState::Checked,
+            CheckState::Checked => CheckState::Unchecked,
+        }
+    }
+
+    pub fn is_checked(self) -> bool {
+        self == CheckState::Checked
+    }
+}
+
+impl From<bool> for CheckState {
+    fn from(checked: bool) -> Self {
+        if checked {
+            CheckState::Checked
+        } else {
+            CheckState::Unchecked
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct CheckBox {
     pub label: Option<String>,
-    pub checked: bool,
+    pub state: CheckState,
+    // Allows `CheckState::Mixed` to be shown as an inconsistent checkbox
+    pub tri_state: bool,
+    // In controlled mode the parent owns `state`: toggling only requests a new state through
+    // `on_toggled` and the checkbox waits for the parent to pass it back. Otherwise `state` is
+    // just the initial value and the checkbox keeps track of it itself.
+    pub controlled: bool,
     pub spacing: i32,
     pub widget_name: String,
     pub label_widget: String,
-    pub on_toggled: Callback<bool>,
+    // Dimmed line under the box
+    pub help_text: Option<String>,
+    pub help_widget: String,
+    pub tooltip: Option<String>,
+    pub sensitive: bool,
+    // Shows the state without letting the user change it, unlike `sensitive=false` the box
+    // doesn't look disabled
+    pub read_only: bool,
+    pub on_toggled: Callback<CheckState>,
+    // Last state given by the parent, used to spot changes in uncontrolled mode
+    prop_state: CheckState,
 }
 
 impl Default for CheckBox {
     fn default() -> Self {
         Self {
             label: None,
-            checked: false,
+            state: CheckState::Unchecked,
+            tri_state: false,
+            controlled: false,
             spacing: 10,
             widget_name: String::new(),
             label_widget: String::new(),
+            help_text: None,
+            help_widget: String::from("help"),
+            tooltip: None,
+            sensitive: true,
+            read_only: false,
             on_toggled: Callback::default(),
+            prop_state: CheckState::Unchecked,
         }
     }
 }
 
 #[derive(Clone, Debug)]
 pub enum CheckBoxMessage {
-    Toggle,
+    // Sent on every `toggled` signal with the button's new `active` value
+    Toggle { active: bool },
+}
+
+/// Works out what a `toggled` signal means given the state last rendered. Returns the state the
+/// user asked for, or None when the signal only echoes a render setting `active`.
+fn requested_state(rendered: CheckState, active: bool) -> Option<CheckState> {
+    if active == rendered.is_checked() {
+        None
+    } else {
+        Some(rendered.toggled())
+    }
 }
 
 impl Component for CheckBox {
@@ -35,34 +107,136 @@ impl Component for CheckBox {
     type Properties = Self;
 
     fn create(props: Self::Properties) -> Self {
-        props
+        let mut component = props;
+        component.prop_state = component.state;
+        component
     }
 
     fn change(&mut self, props: Self::Properties) -> UpdateAction<Self> {
+        let state = self.state;
+        let prop_state = self.prop_state;
+
         *self = props;
+        self.prop_state = self.state;
+
+        // Uncontrolled checkboxes keep their own state unless the parent passes a different one
+        if !self.controlled && self.state == prop_state {
+            self.state = state;
+        }
+
         UpdateAction::Render
     }
 
     fn update(&mut self, msg: Self::Message) -> UpdateAction<Self> {
         match msg {
-            CheckBoxMessage::Toggle => {
-                self.checked = !self.checked;
-                self.on_toggled.send(self.checked);
-                UpdateAction::Render
-            }
+            CheckBoxMessage::Toggle { active } => match requested_state(self.state, active) {
+                // Puts the button back
+                Some(_) if self.read_only => UpdateAction::Render,
+                Some(state) => {
+                    if !self.controlled {
+                        self.state = state;
+                    }
+                    self.on_toggled.send(state);
+                    // In controlled mode this puts the button back until the parent answers
+                    UpdateAction::Render
+                }
+                None => UpdateAction::None,
+            },
         }
     }
 
     fn view(&self) -> VNode<Self> {
+        let inconsistent = self.tri_state && self.state == CheckState::Mixed;
         gtk! {
-            <Box orientation=Orientation::Horizontal spacing=self.spacing>
-                <CheckButton active=self.checked widget_name=self.widget_name.clone() on toggled=|_| CheckBoxMessage::Toggle />
-                {
-                    gtk_if!(self.label.is_some() => {
-                        <Label label=self.label.as_ref().unwrap().to_string() widget_name=self.label_widget.clone() />
-                    })
-                }
+            <Box orientation=Orientation::Vertical spacing=4 sensitive=self.sensitive
+                property_tooltip_text=self.tooltip.clone()>
+                <Box orientation=Orientation::Horizontal spacing=self.spacing>
+                    <CheckButton active=self.state.is_checked() inconsistent=inconsistent widget_name=self.widget_name.clone()
+                        on toggled=|button| CheckBoxMessage::Toggle { active: button.get_active() } />
+                    {
+                        gtk_if!(self.label.is_some() => {
+                            <Label label=self.label.as_ref().unwrap().to_string() widget_name=self.label_widget.clone()
+                                selectable=self.read_only />
+                        })
+                    }
+                </Box>
+                { help_view(self.help_text.clone(), self.help_widget.clone()) }
             </Box>
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    fn props(state: CheckState, controlled: bool, sent: &Rc<RefCell<Vec<CheckState>>>) -> CheckBox {
+        let sent = sent.clone();
+        CheckBox {
+            state,
+            controlled,
+            on_toggled: Callback::from(move |state: CheckState| sent.borrow_mut().push(state)),
+            ..CheckBox::default()
+        }
+    }
+
+    #[test]
+    fn requested_state_ignores_echoes() {
+        assert_eq!(requested_state(CheckState::Unchecked, false), None);
+        assert_eq!(requested_state(CheckState::Unchecked, true), Some(CheckState::Checked));
+        assert_eq!(requested_state(CheckState::Checked, true), None);
+        assert_eq!(requested_state(CheckState::Checked, false), Some(CheckState::Unchecked));
+        assert_eq!(requested_state(CheckState::Mixed, false), None);
+        assert_eq!(requested_state(CheckState::Mixed, true), Some(CheckState::Checked));
+    }
+
+    #[test]
+    fn controlled_toggle_waits_for_parent() {
+        let sent = Rc::new(RefCell::new(vec![]));
+        let mut checkbox = CheckBox::create(props(CheckState::Unchecked, true, &sent));
+
+        assert!(matches!(checkbox.update(CheckBoxMessage::Toggle { active: true }), UpdateAction::Render));
+        assert_eq!(checkbox.state, CheckState::Unchecked);
+        assert_eq!(*sent.borrow(), vec![CheckState::Checked]);
+
+        // Rendering the old state again puts the button back
+        assert!(matches!(checkbox.update(CheckBoxMessage::Toggle { active: false }), UpdateAction::None));
+
+        // The parent accepts the new state
+        checkbox.change(props(CheckState::Checked, true, &sent));
+        assert_eq!(checkbox.state, CheckState::Checked);
+        assert!(matches!(checkbox.update(CheckBoxMessage::Toggle { active: true }), UpdateAction::None));
+        assert_eq!(*sent.borrow(), vec![CheckState::Checked]);
+    }
+
+    #[test]
+    fn uncontrolled_toggle_sends_once() {
+        let sent = Rc::new(RefCell::new(vec![]));
+        let mut checkbox = CheckBox::create(props(CheckState::Unchecked, false, &sent));
+
+        assert!(matches!(checkbox.update(CheckBoxMessage::Toggle { active: true }), UpdateAction::Render));
+        assert_eq!(checkbox.state, CheckState::Checked);
+
+        // The parent passes the new state back and the render fires `toggled` again
+        checkbox.change(props(CheckState::Checked, false, &sent));
+        assert_eq!(checkbox.state, CheckState::Checked);
+        assert!(matches!(checkbox.update(CheckBoxMessage::Toggle { active: true }), UpdateAction::None));
+        assert_eq!(*sent.borrow(), vec![CheckState::Checked]);
+    }
+
+    #[test]
+    fn read_only_reverts_toggle() {
+        let sent = Rc::new(RefCell::new(vec![]));
+        let mut checkbox = CheckBox::create(CheckBox {
+            read_only: true,
+            ..props(CheckState::Unchecked, false, &sent)
+        });
+
+        assert!(matches!(checkbox.update(CheckBoxMessage::Toggle { active: true }), UpdateAction::Render));
+        assert_eq!(checkbox.state, CheckState::Unchecked);
+        assert!(sent.borrow().is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+use std::fmt::Debug;
+
+use vgtk::lib::gtk::prelude::*;
+use vgtk::lib::gtk::*;
+use vgtk::{gtk, gtk_if, Callback, Component, UpdateAction, VNode};
+
+use crate::labeled_field::{FieldLayout, LabeledField};
+
+/// One choice in a `CheckBoxGroup` or `RadioGroup`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde_crate::Serialize, serde_crate::Deserialize), serde(crate = "serde_crate"))]
+pub struct GroupOption<T> {
+    pub value: T,
+    pub label: String,
+    // Optional line shown under the label
+    pub description: Option<String>,
+}
+
+impl<T> GroupOption<T> {
+    pub fn new(value: T, label: &str) -> Self {
+        Self {
+            value,
+            label: label.to_string(),
+            description: None,
+        }
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+}
+
+/// What is selected in a `CheckBoxGroup`, `RadioGroup` or `Dropdown`, as a typed value that can
+/// be stored, e.g. in a draft.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde_crate::Serialize, serde_crate::Deserialize), serde(crate = "serde_crate"))]
+pub struct Selection<T> {
+    // Positions of the selected options, in the order of the options
+    pub indices: Vec<usize>,
+    pub values: Vec<T>,
+}
+
+impl<T> Default for Selection<T> {
+    fn default() -> Self {
+        Self {
+            indices: vec![],
+            values: vec![],
+        }
+    }
+}
+
+impl<T> Selection<T> {
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CheckBoxGroup<T> {
+    pub label: Option<String>,
+    pub options: Vec<GroupOption<T>>,
+    // Initially checked values, the group keeps track of them afterwards
+    pub selected: Vec<T>,
+    pub orientation: Orientation,
+    pub spacing: i32,
+    pub widget_name: String,
+    pub label_widget: String,
+    pub help_text: Option<String>,
+    pub description_widget: String,
+    pub tooltip: Option<String>,
+    pub sensitive: bool,
+    // Shows the choices without letting the user change them
+    pub read_only: bool,
+    pub on_changed: Callback<Vec<T>>,
+    // Same as `on_changed`, with the options' positions
+    pub on_selection: Callback<Selection<T>>,
+    // Last selection given by the parent, used to spot changes
+    prop_selected: Vec<T>,
+}
+
+impl<T> Default for CheckBoxGroup<T> {
+    fn default() -> Self {
+        Self {
+            label: None,
+            options: vec![],
+            selected: vec![],
+            orientation: Orientation::Vertical,
+            spacing: 10,
+            widget_name: String::new(),
+            label_widget: String::new(),
+            help_text: None,
+            description_widget: String::from("description"),
+            tooltip: None,
+            sensitive: true,
+            read_only: false,
+            on_changed: Callback::default(),
+            on_selection: Callback::default(),
+            prop_selected: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum CheckBoxGroupMessage {
+    Toggle { index: usize, active: bool },
+    // Sent by the layout's realize handler, see `LabeledField::realized`
+    #[doc(hidden)]
+    Realized,
+}
+
+impl<T: Clone + Debug + PartialEq + Unpin + 'static> CheckBoxGroup<T> {
+    fn is_selected(&self, value: &T) -> bool {
+        self.selected.contains(value)
+    }
+}
+
+impl<T: Clone + Debug + PartialEq + Unpin + 'static> Component for CheckBoxGroup<T> {
+    type Message = CheckBoxGroupMessage;
+    type Properties = Self;
+
+    fn create(props: Self::Properties) -> Self {
+        let mut component = props;
+        component.prop_selected = component.selected.clone();
+        component
+    }
+
+    fn change(&mut self, props: Self::Properties) -> UpdateAction<Self> {
+        let selected = std::mem::take(&mut self.selected);
+        let prop_selected = std::mem::take(&mut self.prop_selected);
+
+        *self = props;
+        self.prop_selected = self.selected.clone();
+
+        // Keep the user's choices unless the parent passes a different selection
+        if self.selected == prop_selected {
+            self.selected = selected;
+        }
+
+        UpdateAction::Render
+    }
+
+    fn update(&mut self, msg: Self::Message) -> UpdateAction<Self> {
+        match msg {
+            CheckBoxGroupMessage::Toggle { index, active } => {
+                let value = match self.options.get(index) {
+                    Some(option) => option.value.clone(),
+                    None => return UpdateAction::None,
+                };
+
+                // Rendering `active` fires `toggled` as well, only react to real changes
+                if active == self.is_selected(&value) {
+                    return UpdateAction::None;
+                }
+
+                // Puts the button back
+                if self.read_only {
+                    return UpdateAction::Render;
+                }
+
+                if active {
+                    self.selected.push(value);
+                } else {
+                    self.selected.retain(|selected| *selected != value);
+                }
+
+                // Emit the values in the order of the options
+                let (indices, values) = self
+                    .options
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, option)| self.is_selected(&option.value))
+                    .map(|(index, option)| (index, option.value.clone()))
+                    .unzip();
+                let selection = Selection { indices, values };
+                self.on_changed.send(selection.values.clone());
+                self.on_selection.send(selection);
+                UpdateAction::Render
+            }
+            CheckBoxGroupMessage::Realized => UpdateAction::None,
+        }
+    }
+
+    fn view(&self) -> VNode<Self> {
+        self.labeled(gtk! {
+            <Box orientation=self.orientation spacing=self.spacing widget_name=self.widget_name.clone()>
+            {
+                self.options.iter().enumerate().map(|(index, option)| gtk! {
+                    <Box orientation=Orientation::Vertical>
+                        <CheckButton label=option.label.clone() active=self.is_selected(&option.value)
+                            on toggled=|button| CheckBoxGroupMessage::Toggle { index, active: button.get_active() } />
+                        {
+                            gtk_if!(option.description.is_some() => {
+                                <Label label=option.description.clone().unwrap() widget_name=self.description_widget.clone()
+                                    halign=Align::Start />
+                            })
+                        }
+                    </Box>
+                })
+            }
+            </Box>
+        })
+    }
+}
+
+impl<T: Clone + Debug + PartialEq + Unpin + 'static> LabeledField for CheckBoxGroup<T> {
+    fn layout(&self) -> FieldLayout {
+        FieldLayout {
+            label: self.label.clone(),
+            orientation: Orientation::Vertical,
+            spacing: self.spacing,
+            label_widget: self.label_widget.clone(),
+            help_text: self.help_text.clone(),
+            tooltip: self.tooltip.clone(),
+            sensitive: self.sensitive,
+            ..FieldLayout::default()
+        }
+    }
+
+    fn realized() -> Self::Message {
+        CheckBoxGroupMessage::Realized
+    }
+}
@@ -1,11 +1,12 @@
 use std::fmt;
 
-use chrono::{Datelike, Utc};
+use chrono::{Datelike, NaiveDate, Utc};
 use lazy_static::lazy_static;
 use vgtk::lib::gtk::*;
-use vgtk::{gtk, gtk_if, Callback, Component, UpdateAction, VNode};
+use vgtk::{gtk, Callback, Component, UpdateAction, VNode};
 
 use crate::dropdown::Dropdown;
+use crate::labeled_field::{read_only_view, FieldLayout, LabeledField};
 
 lazy_static! {
     static ref CURRENT_YEAR: usize = {
@@ -28,7 +29,8 @@ lazy_static! {
     .collect();
 }
 
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Default, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde_crate::Serialize, serde_crate::Deserialize), serde(crate = "serde_crate"))]
 pub struct Date {
     pub month: String,
     pub day: String,
@@ -45,6 +47,24 @@ impl fmt::Display for Date {
     }
 }
 
+impl Date {
+    /// The date, if all parts are filled in and valid.
+    pub fn to_naive_date(&self) -> Option<NaiveDate> {
+        let month = MONTHS.iter().position(|month| *month == self.month)? + 1;
+        NaiveDate::from_ymd_opt(self.year.parse().ok()?, month as u32, self.day.parse().ok()?)
+    }
+}
+
+impl From<NaiveDate> for Date {
+    fn from(date: NaiveDate) -> Self {
+        Self {
+            month: MONTHS[date.month0() as usize].clone(),
+            day: format!("{:02}", date.day()),
+            year: date.year().to_string(),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct DateInput {
     pub label: Option<String>,
@@ -64,6 +84,15 @@ pub struct DateInput {
     pub month_widget: String,
     pub day_widget: String,
     pub label_widget: String,
+    // An underscore in the label marks the Alt shortcut that focuses the input
+    pub mnemonic: bool,
+    pub help_text: Option<String>,
+    pub tooltip: Option<String>,
+    pub required: bool,
+    pub error: Option<String>,
+    pub sensitive: bool,
+    // Same as `editable=false`
+    pub read_only: bool,
     pub on_update: Callback<Date>,
     years: Vec<String>,
 }
@@ -88,6 +117,13 @@ impl Default for DateInput {
             month_widget: String::from("month"),
             day_widget: String::from("day"),
             label_widget: String::from("label"),
+            mnemonic: false,
+            help_text: None,
+            tooltip: None,
+            required: false,
+            error: None,
+            sensitive: true,
+            read_only: false,
             on_update: Callback::default(),
             years: vec![],
         }
@@ -99,6 +135,9 @@ pub enum DateInputMessage {
     SetMonth { month: String },
     SetDay { day: String },
     SetYear { year: String },
+    // Sent by the layout's realize handlers, see `LabeledField::realized`
+    #[doc(hidden)]
+    Realized,
 }
 
 impl Component for DateInput {
@@ -151,46 +190,65 @@ impl Component for DateInput {
                 self.on_update.send(self.date.clone());
                 UpdateAction::None
             }
+            DateInputMessage::Realized => UpdateAction::None,
         }
     }
 
     fn view(&self) -> VNode<Self> {
-        gtk! {
-            <Box orientation=Orientation::Horizontal spacing=self.spacing widget_name=self.widget_name.clone()>
-            {
-                gtk_if!(self.label.is_some() => {
-                    <Label label=self.label.as_ref().unwrap().to_string() widget_name=self.label_widget.clone()
-                        halign=Align::Start />
-                })
-            }
-            {
-                let pack_type = if self.full_width { PackType::End } else { PackType::Start };
-                if self.editable {
-                    gtk! {
-                        <Box orientation=Orientation::Horizontal spacing=self.spacing widget_name=self.widget_name.clone() Box::pack_type=pack_type>
-                            <Box orientation=self.orientation spacing=self.element_spacing widget_name=self.month_widget.clone()>
-                                <Label label=self.month_label.clone() halign=Align::Start />
-                                <@Dropdown items=MONTHS.clone() on_select=|month| DateInputMessage::SetMonth { month } />
-                            </Box>
-                            <Box orientation=self.orientation spacing=self.element_spacing widget_name=self.day_widget.clone()>
-                                <Label label=self.day_label.clone() halign=Align::Start />
-                                <@Dropdown items=DAYS.clone() on_select=|day| DateInputMessage::SetDay { day } />
-                            </Box>
-                            <Box orientation=self.orientation spacing=self.element_spacing widget_name=self.year_widget.clone()>
-                                <Label label=self.year_label.clone() halign=Align::Start />
-                                <@Dropdown items=self.years.clone() on_select=|year| DateInputMessage::SetYear { year } />
-                            </Box>
-                        </Box>
-                    }
-                } else {
-                    gtk! {
-                        <Box orientation=Orientation::Horizontal spacing=10 Box::pack_type=pack_type>
-                            <Label label=self.date.to_string() widget_name=self.widget_name.clone() />
-                        </Box>
-                    }
-                }
-            }
-            </Box>
+        if self.editable && !self.read_only {
+            self.labeled(gtk! {
+                <Box orientation=Orientation::Horizontal spacing=self.spacing widget_name=self.widget_name.clone()>
+                    <Box orientation=self.orientation spacing=self.element_spacing widget_name=self.month_widget.clone()>
+                        <Label label=self.month_label.clone() halign=Align::Start />
+                        <@Dropdown items=MONTHS.clone() selected=non_empty(&self.date.month)
+                            on_select=|month| DateInputMessage::SetMonth { month: month.unwrap_or_default() } />
+                    </Box>
+                    <Box orientation=self.orientation spacing=self.element_spacing widget_name=self.day_widget.clone()>
+                        <Label label=self.day_label.clone() halign=Align::Start />
+                        <@Dropdown items=DAYS.clone() selected=non_empty(&self.date.day)
+                            on_select=|day| DateInputMessage::SetDay { day: day.unwrap_or_default() } />
+                    </Box>
+                    <Box orientation=self.orientation spacing=self.element_spacing widget_name=self.year_widget.clone()>
+                        <Label label=self.year_label.clone() halign=Align::Start />
+                        <@Dropdown items=self.years.clone() selected=non_empty(&self.date.year)
+                            on_select=|year| DateInputMessage::SetYear { year: year.unwrap_or_default() } />
+                    </Box>
+                </Box>
+            })
+        } else {
+            self.labeled(read_only_view(self.date.to_string(), self.widget_name.clone()))
         }
     }
 }
+
+impl LabeledField for DateInput {
+    fn layout(&self) -> FieldLayout {
+        FieldLayout {
+            label: self.label.clone(),
+            // `orientation` applies to the year, month and day parts
+            orientation: Orientation::Horizontal,
+            spacing: self.spacing,
+            full_width: self.full_width,
+            label_widget: self.label_widget.clone(),
+            mnemonic: self.mnemonic,
+            help_text: self.help_text.clone(),
+            required: self.required,
+            error: self.error.clone(),
+            tooltip: self.tooltip.clone(),
+            sensitive: self.sensitive,
+            ..FieldLayout::default()
+        }
+    }
+
+    fn realized() -> Self::Message {
+        DateInputMessage::Realized
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
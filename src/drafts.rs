@@ -0,0 +1,230 @@
+//! Saving and loading component values, e.g. to autosave a form's `FormValues` as a draft.
+//! Only available with the `serde` feature.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde_crate::de::DeserializeOwned;
+use serde_crate::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DraftFormat {
+    Json,
+    // Can't hold `null`, so not for `SchemaForm` values with empty numbers or enums
+    Toml,
+}
+
+impl DraftFormat {
+    /// Picks the format from a `.json` or `.toml` extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "json" => Some(DraftFormat::Json),
+            "toml" => Some(DraftFormat::Toml),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DraftError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    TomlWrite(toml::ser::Error),
+    TomlRead(toml::de::Error),
+    UnknownFormat,
+}
+
+impl fmt::Display for DraftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DraftError::Io(error) => write!(f, "Couldn't access the draft: {}", error),
+            DraftError::Json(error) => write!(f, "Invalid JSON draft: {}", error),
+            DraftError::TomlWrite(error) => write!(f, "Couldn't write the TOML draft: {}", error),
+            DraftError::TomlRead(error) => write!(f, "Invalid TOML draft: {}", error),
+            DraftError::UnknownFormat => write!(f, "Drafts have to be .json or .toml files"),
+        }
+    }
+}
+
+impl Error for DraftError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DraftError::Io(error) => Some(error),
+            DraftError::Json(error) => Some(error),
+            DraftError::TomlWrite(error) => Some(error),
+            DraftError::TomlRead(error) => Some(error),
+            DraftError::UnknownFormat => None,
+        }
+    }
+}
+
+impl From<io::Error> for DraftError {
+    fn from(error: io::Error) -> Self {
+        DraftError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for DraftError {
+    fn from(error: serde_json::Error) -> Self {
+        DraftError::Json(error)
+    }
+}
+
+impl From<toml::ser::Error> for DraftError {
+    fn from(error: toml::ser::Error) -> Self {
+        DraftError::TomlWrite(error)
+    }
+}
+
+impl From<toml::de::Error> for DraftError {
+    fn from(error: toml::de::Error) -> Self {
+        DraftError::TomlRead(error)
+    }
+}
+
+pub fn to_string<T: Serialize>(value: &T, format: DraftFormat) -> Result<String, DraftError> {
+    Ok(match format {
+        DraftFormat::Json => serde_json::to_string_pretty(value)?,
+        DraftFormat::Toml => toml::to_string_pretty(value)?,
+    })
+}
+
+pub fn from_str<T: DeserializeOwned>(text: &str, format: DraftFormat) -> Result<T, DraftError> {
+    Ok(match format {
+        DraftFormat::Json => serde_json::from_str(text)?,
+        DraftFormat::Toml => toml::from_str(text)?,
+    })
+}
+
+/// Writes the value in the format given by the path's extension. The file is replaced in one
+/// step, so a crash while saving doesn't leave a broken draft behind.
+pub fn save_draft<T: Serialize>(path: &Path, value: &T) -> Result<(), DraftError> {
+    let format = DraftFormat::from_path(path).ok_or(DraftError::UnknownFormat)?;
+    let text = to_string(value, format)?;
+
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    fs::write(&temporary, text)?;
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+/// Reads a draft saved by `save_draft`, `None` if there is none.
+pub fn load_draft<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, DraftError> {
+    let format = DraftFormat::from_path(path).ok_or(DraftError::UnknownFormat)?;
+    match fs::read_to_string(path) {
+        Ok(text) => from_str(&text, format).map(Some),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Serializes `Option<String>` as a string that's empty for `None`.
+pub(crate) mod empty_as_none {
+    use serde_crate::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(value.as_deref().unwrap_or_default())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Ok(if value.is_empty() { None } else { Some(value) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::date::Date;
+    use crate::form::{FieldValue, FormValues};
+
+    fn values() -> FormValues {
+        let mut values = FormValues::default();
+        values.set("name", FieldValue::Text(String::from("Ada")));
+        values.set("news", FieldValue::Bool(true));
+        values.set(
+            "birthday",
+            FieldValue::Date(Date {
+                month: String::from("December"),
+                day: String::from("10"),
+                year: String::from("1815"),
+            }),
+        );
+        values.set("color", FieldValue::Choice(Some(String::from("red"))));
+        values.set("size", FieldValue::Choice(None));
+        values
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("vgtk_components_drafts_test_{}_{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn format_from_extension() {
+        assert_eq!(DraftFormat::from_path(Path::new("form.json")), Some(DraftFormat::Json));
+        assert_eq!(DraftFormat::from_path(Path::new("form.TOML")), Some(DraftFormat::Toml));
+        assert_eq!(DraftFormat::from_path(Path::new("form.yaml")), None);
+        assert_eq!(DraftFormat::from_path(Path::new("form")), None);
+    }
+
+    #[test]
+    fn values_round_trip() {
+        for format in [DraftFormat::Json, DraftFormat::Toml].iter().copied() {
+            let text = to_string(&values(), format).unwrap();
+            assert_eq!(from_str::<FormValues>(&text, format).unwrap(), values(), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn empty_choices_are_saved_as_empty_strings() {
+        let mut values = FormValues::default();
+        values.set("size", FieldValue::Choice(None));
+        let text = to_string(&values, DraftFormat::Json).unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&text).unwrap()["size"]["value"], "");
+
+        let text = to_string(&values, DraftFormat::Toml).unwrap();
+        assert_eq!(toml::from_str::<toml::Value>(&text).unwrap()["size"]["value"].as_str(), Some(""));
+        let loaded: FormValues = from_str(&text, DraftFormat::Toml).unwrap();
+        assert_eq!(loaded.get("size"), Some(&FieldValue::Choice(None)));
+    }
+
+    #[test]
+    fn save_and_load_draft() {
+        for extension in ["json", "toml"].iter() {
+            let path = temp_path(&format!("form.{}", extension));
+            let _ = fs::remove_file(&path);
+            assert_eq!(load_draft::<FormValues>(&path).unwrap(), None);
+
+            save_draft(&path, &values()).unwrap();
+            assert_eq!(load_draft::<FormValues>(&path).unwrap(), Some(values()));
+            // The temporary file was renamed over the draft
+            let mut temporary = path.as_os_str().to_owned();
+            temporary.push(".tmp");
+            assert!(!Path::new(&temporary).exists());
+
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn drafts_need_a_known_format() {
+        let path = temp_path("form.txt");
+        assert!(matches!(save_draft(&path, &values()), Err(DraftError::UnknownFormat)));
+        assert!(matches!(load_draft::<FormValues>(&path), Err(DraftError::UnknownFormat)));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn invalid_drafts_are_errors() {
+        let path = temp_path("broken.json");
+        fs::write(&path, "{ \"name\": ").unwrap();
+        assert!(matches!(load_draft::<FormValues>(&path), Err(DraftError::Json(_))));
+        fs::remove_file(&path).unwrap();
+    }
+}
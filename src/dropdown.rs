@@ -1,38 +1,314 @@
-use vgtk::lib::glib::{Type, Value};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use vgtk::lib::glib::{ThreadPool, Type};
 use vgtk::lib::gtk::prelude::*;
 use vgtk::lib::gtk::*;
 use vgtk::{gtk, gtk_if, Callback, Component, UpdateAction, VNode};
 
+use crate::checkbox_group::Selection;
+use crate::labeled_field::{read_only_view, FieldLayout, LabeledField};
+
+/// A batch of items returned by an `ItemSource`.
+#[derive(Clone, Debug, Default)]
+pub struct ItemPage {
+    pub items: Vec<String>,
+    // Whether another page can be requested after this one
+    pub has_more: bool,
+}
+
+/// Provides dropdown items asynchronously. The returned future is polled on the glib main
+/// context, so it must not block; see `BlockingSource` for loaders doing blocking I/O.
+pub trait ItemSource: fmt::Debug {
+    /// Loads up to `limit` items starting at `offset`. An error is shown under the dropdown
+    /// along with a row to retry the page.
+    fn load(&self, offset: usize, limit: usize) -> Pin<Box<dyn Future<Output = Result<ItemPage, String>>>>;
+}
+
+/// An `ItemSource` running a blocking loader (database query, filesystem scan) on a worker
+/// thread so the UI doesn't freeze.
+pub struct BlockingSource<F> {
+    loader: Arc<F>,
+    pool: ThreadPool,
+}
+
+impl<F> BlockingSource<F>
+where
+    F: Fn(usize, usize) -> Result<ItemPage, String> + Send + Sync + 'static,
+{
+    pub fn new(loader: F) -> Self {
+        Self {
+            loader: Arc::new(loader),
+            pool: ThreadPool::new_exclusive(1).expect("Failed to create item loader thread"),
+        }
+    }
+}
+
+impl<F> fmt::Debug for BlockingSource<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockingSource").finish()
+    }
+}
+
+impl<F> ItemSource for BlockingSource<F>
+where
+    F: Fn(usize, usize) -> Result<ItemPage, String> + Send + Sync + 'static,
+{
+    fn load(&self, offset: usize, limit: usize) -> Pin<Box<dyn Future<Output = Result<ItemPage, String>>>> {
+        let loader = self.loader.clone();
+        match self.pool.push_future(move || loader(offset, limit)) {
+            Ok(page) => Box::pin(page),
+            Err(error) => {
+                let error = error.to_string();
+                Box::pin(async move { Err(error) })
+            }
+        }
+    }
+}
+
+// Ids of the rows in the model's second column, telling items apart from the extra rows even
+// when an item has the same text
+const ITEM_ROW: &str = "item";
+const PLACEHOLDER_ROW: &str = "placeholder";
+const LOAD_MORE_ROW: &str = "load-more";
+const RETRY_ROW: &str = "retry";
+
+fn append_row(dropdown_items: &ListStore, text: &str, id: &str) {
+    let iter = dropdown_items.append();
+    dropdown_items.set(&iter, &[0, 1], &[&text, &id]);
+}
+
 #[derive(Clone, Debug)]
 pub struct Dropdown {
     // A list of items to be provided by parent component
     pub items: Vec<String>,
+    // Loads the items asynchronously instead of using `items`
+    pub source: Option<Rc<dyn ItemSource>>,
+    // Number of items requested from the source at a time
+    pub page_size: usize,
+    pub loading_text: String,
+    pub load_more_text: String,
+    pub retry_text: String,
     pub label: Option<String>,
-    pub on_select: Callback<String>,
+    // Item to select, None selects the placeholder row (or nothing without a placeholder)
+    pub selected: Option<String>,
+    // Text of a leading row standing for "no selection", e.g. "— Select —"
+    pub placeholder: Option<String>,
+    pub clearable: bool,
+    pub clear_label: String,
+    pub required: bool,
+    pub required_text: String,
+    pub on_select: Callback<Option<String>>,
+    // Same as `on_select`, with the item's position
+    pub on_selection: Callback<Selection<String>>,
+    pub on_validated: Callback<bool>,
     pub orientation: Orientation,
     pub spacing: i32,
     pub full_width: bool,
+    pub widget_name: String,
+    pub label_widget: String,
+    // An underscore in the label marks the Alt shortcut that focuses the input
+    pub mnemonic: bool,
+    pub help_text: Option<String>,
+    pub tooltip: Option<String>,
+    // Shown under the dropdown, takes precedence over `required_text`
+    pub error: Option<String>,
+    pub sensitive: bool,
+    // Shows the selected item as text instead of the dropdown
+    pub read_only: bool,
     // Internal list of items stored as a ListStore (upcasted to TreeModel)
     dropdown_items: TreeModel,
+    // Internal copy of the current selection
+    active: Option<String>,
+    touched: bool,
+    // Items received from the source so far
+    loaded_items: Vec<String>,
+    loading: bool,
+    has_more: bool,
+    // Why the last page failed to load
+    load_error: Option<String>,
+    // Bumped for every page asked for, and when the source changes so stale pages get dropped
+    request: usize,
 }
 
 impl Default for Dropdown {
     fn default() -> Self {
         Self {
             items: vec![],
+            source: None,
+            page_size: 100,
+            loading_text: String::from("Loading…"),
+            load_more_text: String::from("Load more…"),
+            retry_text: String::from("Retry"),
             label: None,
+            selected: None,
+            placeholder: None,
+            clearable: false,
+            clear_label: String::from("Clear"),
+            required: false,
+            required_text: String::from("This field is required"),
             on_select: Callback::default(),
+            on_selection: Callback::default(),
+            on_validated: Callback::default(),
             orientation: Orientation::Horizontal,
             spacing: 10,
             full_width: false,
-            dropdown_items: ListStore::new(&[Type::String]).upcast::<TreeModel>(),
+            widget_name: String::new(),
+            label_widget: String::new(),
+            mnemonic: false,
+            help_text: None,
+            tooltip: None,
+            error: None,
+            sensitive: true,
+            read_only: false,
+            dropdown_items: ListStore::new(&[Type::String, Type::String]).upcast::<TreeModel>(),
+            active: None,
+            touched: false,
+            loaded_items: vec![],
+            loading: false,
+            has_more: false,
+            load_error: None,
+            request: 0,
         }
     }
 }
 
 #[derive(Clone, Debug)]
 pub enum DropdownMessage {
-    SetValue { value: String },
+    SetValue { value: Option<String> },
+    Clear,
+    Load,
+    LoadMore,
+    Loaded { page: Result<ItemPage, String>, request: usize },
+    // Sent by the layout's realize handlers, see `LabeledField::realized`
+    #[doc(hidden)]
+    Realized,
+}
+
+impl Dropdown {
+    /// Returns false if the dropdown is required and nothing is selected.
+    pub fn is_valid(&self) -> bool {
+        !self.required || self.active.is_some()
+    }
+
+    // Items shown in the dropdown, either given by the parent or loaded from the source
+    fn entries(&self) -> &Vec<String> {
+        if self.source.is_some() {
+            &self.loaded_items
+        } else {
+            &self.items
+        }
+    }
+
+    fn populate(&mut self) {
+        let dropdown_items = ListStore::new(&[Type::String, Type::String]);
+        if let Some(placeholder) = &self.placeholder {
+            append_row(&dropdown_items, placeholder, PLACEHOLDER_ROW);
+        }
+        for item in self.entries() {
+            append_row(&dropdown_items, item, ITEM_ROW);
+        }
+        if self.load_error.is_some() {
+            append_row(&dropdown_items, &self.retry_text, RETRY_ROW);
+        } else if self.has_more {
+            append_row(&dropdown_items, &self.load_more_text, LOAD_MORE_ROW);
+        }
+        self.dropdown_items = dropdown_items.upcast::<TreeModel>();
+    }
+
+    // Updates the existing model so the current selection survives
+    fn update_rows(&mut self, page: Option<&ItemPage>) {
+        if let Ok(dropdown_items) = self.dropdown_items.clone().downcast::<ListStore>() {
+            // Drop the "Load more…" or "Retry" row, it gets re-added after the new items
+            let rows = dropdown_items.iter_n_children(None);
+            if let Some(iter) = dropdown_items.iter_nth_child(None, rows - 1) {
+                let id = dropdown_items.get_value(&iter, 1).get::<String>().ok().flatten();
+                if id.as_deref() == Some(LOAD_MORE_ROW) || id.as_deref() == Some(RETRY_ROW) {
+                    dropdown_items.remove(&iter);
+                }
+            }
+            for item in page.iter().flat_map(|page| &page.items) {
+                append_row(&dropdown_items, item, ITEM_ROW);
+            }
+            if self.load_error.is_some() {
+                append_row(&dropdown_items, &self.retry_text, RETRY_ROW);
+            } else if page.map_or(self.has_more, |page| page.has_more) {
+                append_row(&dropdown_items, &self.load_more_text, LOAD_MORE_ROW);
+            }
+        }
+    }
+
+    fn append_page(&mut self, page: ItemPage) {
+        self.update_rows(Some(&page));
+        self.loaded_items.extend(page.items);
+        self.has_more = page.has_more;
+    }
+
+    fn send_selection(&self) {
+        let index = self
+            .active
+            .as_ref()
+            .and_then(|active| self.entries().iter().position(|item| item == active));
+        self.on_select.send(self.active.clone());
+        self.on_selection.send(Selection {
+            indices: index.into_iter().collect(),
+            values: self.active.iter().cloned().collect(),
+        });
+    }
+
+    fn active_index(&self) -> Option<u32> {
+        let offset = if self.placeholder.is_some() { 1 } else { 0 };
+        match &self.active {
+            Some(active) => self
+                .entries()
+                .iter()
+                .position(|item| item == active)
+                .map(|index| (index + offset) as u32),
+            None if self.placeholder.is_some() => Some(0),
+            None => None,
+        }
+    }
+
+    // Whether more items can still arrive from the source, so a selected item that isn't loaded
+    // yet may be on a later page
+    fn items_pending(&self) -> bool {
+        self.source.is_some() && (self.loading || self.has_more || self.loaded_items.is_empty())
+    }
+
+    // Clears a selected item that isn't among the items, once no more items can arrive
+    fn drop_missing_active(&mut self) {
+        let missing = matches!(&self.active, Some(active) if !self.entries().contains(active));
+        if missing && !self.items_pending() {
+            self.active = None;
+            self.send_selection();
+            self.on_validated.send(self.is_valid());
+        }
+    }
+
+    // The page is loaded by the `PageLoader` once the new request is rendered, so the loading row
+    // shows up right away
+    fn load_page(&mut self) -> UpdateAction<Self> {
+        if self.source.is_none() || self.loading {
+            return UpdateAction::None;
+        }
+        self.loading = true;
+        self.request += 1;
+        if self.load_error.take().is_some() {
+            self.update_rows(None);
+        }
+        UpdateAction::Render
+    }
+
+    fn loader_view(&self) -> VNode<Self> {
+        gtk! {
+            <@PageLoader source=self.source.clone() request=self.request offset=self.loaded_items.len()
+                limit=self.page_size
+                on_loaded=|loaded| DropdownMessage::Loaded { request: loaded.0, page: loaded.1 } />
+        }
+    }
 }
 
 impl Component for Dropdown {
@@ -41,69 +317,235 @@ impl Component for Dropdown {
 
     fn create(props: Self::Properties) -> Self {
         let mut component = props;
-
-        let dropdown_items = ListStore::new(&[Type::String]);
-        for item in &component.items {
-            let iter = dropdown_items.append();
-            dropdown_items.set_value(&iter, 0, &Value::from(&item));
-        }
-        component.dropdown_items = dropdown_items.upcast::<TreeModel>();
-
+        component.active = component.selected.clone();
+        component.populate();
         component
     }
 
     fn change(&mut self, props: Self::Properties) -> UpdateAction<Self> {
+        let same_source = match (&self.source, &props.source) {
+            (Some(current), Some(new)) => Rc::ptr_eq(current, new),
+            (None, None) => true,
+            _ => false,
+        };
+        let rebuild = !same_source || self.items != props.items || self.placeholder != props.placeholder;
+        let reselect = self.selected != props.selected;
+        let dropdown_items = self.dropdown_items.clone();
+        let active = self.active.take();
+        let touched = self.touched;
+        let loaded_items = std::mem::take(&mut self.loaded_items);
+        let (loading, has_more, request) = (self.loading, self.has_more, self.request);
+        let load_error = self.load_error.take();
+
         *self = props;
+        self.touched = touched;
+        if same_source {
+            self.loaded_items = loaded_items;
+            self.loading = loading;
+            self.has_more = has_more;
+            self.load_error = load_error;
+            self.request = request;
+        } else {
+            self.request = request + 1;
+        }
 
-        // Populate the dropdown with the given items
-        let dropdown_items = ListStore::new(&[Type::String]);
-        for item in &self.items {
-            let iter = dropdown_items.append();
-            dropdown_items.set_value(&iter, 0, &Value::from(&item));
+        // Only rebuild the model when the items change, otherwise the selection gets reset
+        if rebuild {
+            self.populate();
+        } else {
+            self.dropdown_items = dropdown_items;
+        }
+
+        // Keep the user's selection unless the parent asks for a different one
+        if reselect {
+            self.active = self.selected.clone();
+        } else {
+            self.active = active;
+            // The selected item is gone from the items, let the parent know
+            self.drop_missing_active();
         }
-        self.dropdown_items = dropdown_items.upcast::<TreeModel>();
 
+        if !same_source {
+            self.load_page();
+        }
         UpdateAction::Render
     }
 
     fn update(&mut self, msg: Self::Message) -> UpdateAction<Self> {
-        match msg {
-            DropdownMessage::SetValue { value } => {
-                self.on_select.send(value);
-                UpdateAction::None
+        let value = match msg {
+            DropdownMessage::SetValue { value } => value,
+            DropdownMessage::Clear => None,
+            DropdownMessage::Load if self.loaded_items.is_empty() => return self.load_page(),
+            DropdownMessage::Load => return UpdateAction::None,
+            DropdownMessage::LoadMore => return self.load_page(),
+            DropdownMessage::Loaded { page, request } => {
+                if request != self.request {
+                    return UpdateAction::None;
+                }
+                self.loading = false;
+                match page {
+                    Ok(page) => self.append_page(page),
+                    Err(error) => {
+                        self.load_error = Some(error);
+                        self.update_rows(None);
+                    }
+                }
+                self.drop_missing_active();
+                return UpdateAction::Render;
             }
+            DropdownMessage::Realized => return UpdateAction::None,
+        };
+
+        // Re-rendering the active row fires `changed` again, ignore it
+        if value == self.active {
+            return UpdateAction::None;
         }
+
+        self.active = value;
+        self.touched = true;
+        self.send_selection();
+        self.on_validated.send(self.is_valid());
+        UpdateAction::Render
     }
 
     fn view(&self) -> VNode<Self> {
-        gtk! {
-            <Box orientation=self.orientation spacing=self.spacing>
+        if self.read_only {
+            return self.labeled(gtk! {
+                <Box orientation=Orientation::Horizontal>
+                    { read_only_view(self.active.clone().unwrap_or_default(), self.widget_name.clone()) }
+                    { self.loader_view() }
+                </Box>
+            });
+        }
+        self.labeled(gtk! {
+            <Box orientation=Orientation::Horizontal spacing=self.spacing>
+                <ComboBoxText model=Some(self.dropdown_items.clone()) active=self.active_index()
+                    widget_name=self.widget_name.clone()
+                    sensitive=!(self.loading && self.loaded_items.is_empty())
+                    on realize=|_| DropdownMessage::Load
+                    on changed=|entry| match entry.get_active_id().as_deref() {
+                        Some(LOAD_MORE_ROW) | Some(RETRY_ROW) => DropdownMessage::LoadMore,
+                        Some(ITEM_ROW) => DropdownMessage::SetValue {
+                            value: entry.get_active_text().map(|text| text.to_string()),
+                        },
+                        _ => DropdownMessage::SetValue { value: None },
+                    } />
                 {
-                    gtk_if!(self.label.is_some() => {
-                        <Label label=self.label.as_ref().unwrap().to_string() halign=Align::Start />
+                    gtk_if!(self.loading => {
+                        <Box orientation=Orientation::Horizontal spacing=self.spacing>
+                            <Spinner property_active=true />
+                            <Label label=self.loading_text.clone() />
+                        </Box>
                     })
                 }
-            {
-                if self.full_width {
-                    gtk! {
-                        <ComboBoxText model=Some(self.dropdown_items.clone()) Box::pack_type=PackType::End
-                            on changed=|entry| {
-                                DropdownMessage::SetValue {
-                                    value: entry.get_active_text().unwrap().to_string(),
-                                }
-                            } />
-                    }
-                } else {
-                    gtk! {
-                        <ComboBoxText model=Some(self.dropdown_items.clone()) on changed=|entry| {
-                            DropdownMessage::SetValue {
-                                value: entry.get_active_text().unwrap().to_string(),
-                            }
-                        } />
-                    }
+                {
+                    gtk_if!(self.clearable => {
+                        <Button label=self.clear_label.clone() sensitive=self.active.is_some()
+                            on clicked=|_| DropdownMessage::Clear />
+                    })
                 }
-            }
+                { self.loader_view() }
             </Box>
+        })
+    }
+}
+
+impl LabeledField for Dropdown {
+    fn layout(&self) -> FieldLayout {
+        let missing = self.required && self.touched && self.active.is_none();
+        FieldLayout {
+            label: self.label.clone(),
+            orientation: self.orientation,
+            spacing: self.spacing,
+            full_width: self.full_width,
+            label_widget: self.label_widget.clone(),
+            mnemonic: self.mnemonic,
+            help_text: self.help_text.clone(),
+            required: self.required,
+            error: self
+                .error
+                .clone()
+                .or_else(|| self.load_error.clone())
+                .or_else(|| Some(self.required_text.clone()).filter(|_| missing)),
+            tooltip: self.tooltip.clone(),
+            sensitive: self.sensitive,
+            ..FieldLayout::default()
+        }
+    }
+
+    fn realized() -> Self::Message {
+        DropdownMessage::Realized
+    }
+}
+
+// Loads the page the `Dropdown` asks for and hands it back. It lives in its own component because
+// a deferred update doesn't render, so the dropdown couldn't show its loading row and wait for the
+// page at the same time.
+#[derive(Clone, Debug, Default)]
+struct PageLoader {
+    source: Option<Rc<dyn ItemSource>>,
+    // A new value starts loading `limit` items from `offset`
+    request: usize,
+    offset: usize,
+    limit: usize,
+    on_loaded: Callback<(usize, Result<ItemPage, String>)>,
+    // Last request started, so a realize or a render doesn't load the same page twice
+    started: usize,
+}
+
+#[derive(Clone, Debug)]
+enum PageLoaderMessage {
+    Load,
+    Loaded { page: Result<ItemPage, String>, request: usize },
+}
+
+impl PageLoader {
+    fn load(&mut self) -> UpdateAction<Self> {
+        match &self.source {
+            Some(source) if self.request != self.started => {
+                self.started = self.request;
+                let page = source.load(self.offset, self.limit);
+                let request = self.request;
+                UpdateAction::defer(async move {
+                    PageLoaderMessage::Loaded {
+                        page: page.await,
+                        request,
+                    }
+                })
+            }
+            _ => UpdateAction::None,
+        }
+    }
+}
+
+impl Component for PageLoader {
+    type Message = PageLoaderMessage;
+    type Properties = Self;
+
+    fn create(props: Self::Properties) -> Self {
+        props
+    }
+
+    fn change(&mut self, props: Self::Properties) -> UpdateAction<Self> {
+        let started = self.started;
+        *self = props;
+        self.started = started;
+        self.load()
+    }
+
+    fn update(&mut self, msg: Self::Message) -> UpdateAction<Self> {
+        match msg {
+            PageLoaderMessage::Load => self.load(),
+            PageLoaderMessage::Loaded { page, request } => {
+                self.on_loaded.send((request, page));
+                UpdateAction::None
+            }
+        }
+    }
+
+    fn view(&self) -> VNode<Self> {
+        gtk! {
+            <Box visible=false no_show_all=true on realize=|_| PageLoaderMessage::Load />
         }
     }
 }
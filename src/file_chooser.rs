@@ -1,22 +1,163 @@
-use std::path::PathBuf;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 use pango::EllipsizeMode;
+use vgtk::lib::gdk::DragAction;
+use vgtk::lib::gio;
+use vgtk::lib::glib::{self, GString, ThreadPool, Type, WeakRef};
 use vgtk::lib::gtk::prelude::*;
 use vgtk::lib::gtk::*;
 use vgtk::{gtk, gtk_if, Callback, Component, UpdateAction, VNode};
 
+use crate::dropdown::Dropdown;
+use crate::file_dialog::{FileDialogProvider, FileDialogRequest, FileDialogResponse, NativeFileDialog};
+use crate::labeled_field::help_view;
+use crate::paths::{completions_from, expand_path, list_dir, split_typed_path, FolderEntry, PathKind, PathRequirements};
+use crate::recent_files::RecentFiles;
+
+/// A named file type filter offered by the dialog, e.g. "CSV files" with pattern "*.csv".
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileFilterSpec {
+    pub name: String,
+    // Glob patterns such as "*.csv", supporting `*` and `?`
+    pub patterns: Vec<String>,
+    pub mime_types: Vec<String>,
+}
+
+impl FileFilterSpec {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn pattern(mut self, pattern: &str) -> Self {
+        self.patterns.push(pattern.to_string());
+        self
+    }
+
+    pub fn mime_type(mut self, mime_type: &str) -> Self {
+        self.mime_types.push(mime_type.to_string());
+        self
+    }
+
+    /// Returns true if the path matches one of the patterns or MIME types. Folders always match
+    /// so folder selection isn't affected by the filter.
+    pub fn matches(&self, path: &Path) -> bool {
+        if path.is_dir() {
+            return true;
+        }
+
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy(),
+            None => return false,
+        };
+        if self.patterns.iter().any(|pattern| glob_match(pattern, &name)) {
+            return true;
+        }
+
+        if self.mime_types.is_empty() {
+            return false;
+        }
+        let (content_type, _) = gio::content_type_guess(Some(&path.to_string_lossy()), &[]);
+        self.mime_types
+            .iter()
+            .any(|mime_type| gio::content_type_is_mime_type(&content_type, mime_type))
+    }
+
+    /// Extension implied by the first "*.ext" pattern, used to complete file names in save mode.
+    pub fn extension(&self) -> Option<String> {
+        self.patterns.iter().find_map(|pattern| {
+            let extension = pattern.strip_prefix("*.")?;
+            if extension.is_empty() || extension.contains(|c| c == '*' || c == '?') {
+                None
+            } else {
+                Some(extension.to_string())
+            }
+        })
+    }
+
+    pub(crate) fn to_file_filter(&self) -> FileFilter {
+        let filter = FileFilter::new();
+        filter.set_name(Some(&self.name));
+        for pattern in &self.patterns {
+            filter.add_pattern(pattern);
+        }
+        for mime_type in &self.mime_types {
+            filter.add_mime_type(mime_type);
+        }
+        filter
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct FileChooser {
     pub label: Option<String>,
     pub button_label: String,
-    pub dialog_title: &'static str,
-    pub dialog_yes_text: &'static str,
-    pub dialog_no_text: &'static str,
+    pub dialog_title: String,
+    pub dialog_yes_text: String,
+    pub dialog_no_text: String,
     pub dialog_action: FileChooserAction,
+    // Folder the dialog opens in, or a file to select there. In save mode a file's name is
+    // suggested unless `suggested_name` is set, which wins.
+    pub initial_path: Option<PathBuf>,
+    // File type filters offered by the dialog, the first one is active by default
+    pub filters: Vec<FileFilterSpec>,
+    // Adds an "All files" filter after `filters`
+    pub show_all_files: bool,
+    pub all_files_label: String,
+    pub filter_error_text: String,
+    // Save mode settings: file name the dialog starts with
+    pub suggested_name: Option<String>,
+    pub confirm_overwrite: bool,
+    // Asked when a default extension turns the name into an existing file, or an existing file is
+    // dropped, `{path}` is replaced with the path
+    pub overwrite_text: String,
+    // Added in save mode when the file name has no extension and the active filter implies none
+    pub default_extension: Option<String>,
+    // Lets the user pick several files, reported through `on_selected_multiple`
+    pub select_multiple: bool,
+    pub remove_label: String,
+    // Shown instead of the paths when `show_path` is set in multiple selection mode, `{count}`
+    // is replaced with the number of files
+    pub summary_one: String,
+    pub summary_many: String,
+    // Accepts files dropped from a file manager onto the row
+    pub accept_drops: bool,
+    // Keeps a most-recently-used list, offered in a dropdown next to the button
+    pub recent_files: Option<RecentFiles>,
+    pub recent_placeholder: String,
     pub show_path: bool,
+    // Shows the path in an entry the user can type or paste into (single selection only)
+    pub editable_path: bool,
+    // Checks for typed paths, `kind` defaults to what `dialog_action` selects
+    pub path_requirements: PathRequirements,
     pub spacing: i32,
+    // Dimmed line under the chooser
+    pub help_text: Option<String>,
+    pub help_widget: String,
+    pub tooltip: Option<String>,
+    pub sensitive: bool,
+    // Only shows the selected path(s) as selectable text, without the button, recent files
+    // dropdown or path entry
+    pub read_only: bool,
     pub on_selected: Callback<Option<PathBuf>>,
+    pub on_selected_multiple: Callback<Vec<PathBuf>>,
+    // Sent when the dialog is cancelled or replacing a file is declined, the selection is kept
+    pub on_cancel: Callback<()>,
+    // Shows the dialog, replaced by a `ScriptedFileDialog` in headless tests
+    pub dialog_provider: Rc<dyn FileDialogProvider>,
     path: Option<PathBuf>,
+    paths: Vec<PathBuf>,
+    error: Option<String>,
+    recent: Vec<PathBuf>,
+    // Text of the editable path entry
+    path_text: String,
+    completion: Rc<PathCompletion>,
+    // Row accepting drops, kept across `change` to follow `accept_drops`
+    drop_row: Rc<RefCell<Option<WeakRef<Box>>>>,
 }
 
 impl Default for FileChooser {
@@ -24,14 +165,46 @@ impl Default for FileChooser {
         Self {
             label: None,
             button_label: String::from("..."),
-            dialog_title: "Select File",
-            dialog_yes_text: "_Select",
-            dialog_no_text: "_Cancel",
+            dialog_title: String::from("Select File"),
+            dialog_yes_text: String::from("_Select"),
+            dialog_no_text: String::from("_Cancel"),
             dialog_action: FileChooserAction::Open,
+            initial_path: None,
+            filters: vec![],
+            show_all_files: true,
+            all_files_label: String::from("All files"),
+            filter_error_text: String::from("The selected file doesn't match the file type"),
+            suggested_name: None,
+            confirm_overwrite: true,
+            overwrite_text: String::from("A file named \"{path}\" already exists. Do you want to replace it?"),
+            default_extension: None,
+            select_multiple: false,
+            remove_label: String::from("Remove"),
+            summary_one: String::from("1 file selected"),
+            summary_many: String::from("{count} files selected"),
+            accept_drops: true,
+            recent_files: None,
+            recent_placeholder: String::from("Recent…"),
             show_path: false,
+            editable_path: false,
+            path_requirements: PathRequirements::default(),
             spacing: 10,
+            help_text: None,
+            help_widget: String::from("help"),
+            tooltip: None,
+            sensitive: true,
+            read_only: false,
             on_selected: Callback::default(),
+            on_selected_multiple: Callback::default(),
+            on_cancel: Callback::default(),
+            dialog_provider: Rc::new(NativeFileDialog),
             path: None,
+            paths: vec![],
+            error: None,
+            recent: vec![],
+            path_text: String::new(),
+            completion: Rc::default(),
+            drop_row: Rc::default(),
         }
     }
 }
@@ -39,6 +212,117 @@ impl Default for FileChooser {
 #[derive(Clone, Debug)]
 pub enum FileChooserMessage {
     SetPath { path: Option<PathBuf> },
+    SetPaths { paths: Vec<PathBuf> },
+    RemovePath { index: usize },
+    SelectRecent { path: PathBuf },
+    EditPath { text: String },
+    // Validates the typed path, sent on Enter or when the entry loses focus
+    CommitPath,
+    // A folder was listed for the path entry's completion
+    Listed { dir: PathBuf, entries: Vec<FolderEntry> },
+    // The selected file doesn't match the active filter
+    Reject { path: PathBuf },
+    // The dialog was cancelled, keeps the current selection
+    Cancel,
+    // Sent by handlers that only set up widgets, e.g. on realize
+    #[doc(hidden)]
+    Ignore,
+}
+
+impl FileChooser {
+    /// Shows the dialog through `dialog_provider` and returns the message the button sends.
+    pub fn open_dialog(&self) -> FileChooserMessage {
+        self.dialog_options().run()
+    }
+
+    fn dialog_options(&self) -> DialogOptions {
+        let all_files_label = if self.show_all_files && !self.filters.is_empty() {
+            Some(self.all_files_label.clone())
+        } else {
+            None
+        };
+        DialogOptions {
+            request: FileDialogRequest {
+                title: self.dialog_title.clone(),
+                yes_text: self.dialog_yes_text.clone(),
+                no_text: self.dialog_no_text.clone(),
+                action: self.dialog_action,
+                select_multiple: self.select_multiple,
+                filters: self.filters.clone(),
+                all_files_label,
+                initial_path: self.initial_path.clone(),
+                suggested_name: self.suggested_name.clone(),
+                confirm_overwrite: self.confirm_overwrite,
+                overwrite_text: self.overwrite_text.clone(),
+            },
+            provider: self.dialog_provider.clone(),
+            default_extension: self.default_extension.clone(),
+        }
+    }
+
+    fn check_typed_path(&self, path: &Path) -> Result<(), String> {
+        let mut requirements = self.path_requirements.clone();
+        if requirements.kind.is_none() {
+            requirements.kind = match self.dialog_action {
+                FileChooserAction::SelectFolder | FileChooserAction::CreateFolder => Some(PathKind::Dir),
+                FileChooserAction::Save => Some(PathKind::NotDir),
+                _ => Some(PathKind::File),
+            };
+        }
+        requirements.check(path).map_err(|error| error.to_string())?;
+
+        let matches_filter = self.filters.is_empty() || self.filters.iter().any(|filter| filter.matches(path));
+        if path.exists() && !matches_filter {
+            return Err(self.filter_error_text.clone());
+        }
+        Ok(())
+    }
+
+    fn load_recent(&mut self) {
+        self.recent = match &self.recent_files {
+            Some(recent_files) => recent_files.load(),
+            None => vec![],
+        };
+    }
+
+    fn remember<'a>(&mut self, paths: impl IntoIterator<Item = &'a PathBuf>) {
+        if let Some(recent_files) = &self.recent_files {
+            for path in paths {
+                recent_files.add(path);
+            }
+            self.load_recent();
+        }
+    }
+
+    // Shows the completions for the typed path, listing its folder first if needed
+    fn complete_path(&mut self) -> UpdateAction<Self> {
+        let dir = match self.completion.update(&self.path_text) {
+            Some(dir) => dir,
+            None => return UpdateAction::None,
+        };
+        let listed = dir.clone();
+        match LISTING_POOL.with(|pool| pool.push_future(move || list_dir(&listed))) {
+            Ok(entries) => UpdateAction::defer(async move {
+                FileChooserMessage::Listed {
+                    dir,
+                    entries: entries.await,
+                }
+            }),
+            Err(_) => {
+                self.completion.listed(dir, vec![]);
+                UpdateAction::None
+            }
+        }
+    }
+
+    fn accepts_drops(&self) -> bool {
+        self.accept_drops && !self.read_only
+    }
+
+    fn summary(&self) -> String {
+        let format = if self.paths.len() == 1 { &self.summary_one } else { &self.summary_many };
+        format.replace("{count}", &self.paths.len().to_string())
+    }
 }
 
 impl Component for FileChooser {
@@ -46,54 +330,599 @@ impl Component for FileChooser {
     type Properties = Self;
 
     fn create(props: Self::Properties) -> Self {
-        props
+        let mut component = props;
+        component.load_recent();
+        component
     }
 
     fn change(&mut self, props: Self::Properties) -> UpdateAction<Self> {
+        let path = self.path.take();
+        let paths = std::mem::take(&mut self.paths);
+        let error = self.error.take();
+        let path_text = std::mem::take(&mut self.path_text);
+        let completion = self.completion.clone();
+        let recent = std::mem::take(&mut self.recent);
+        let reload = self.recent_files != props.recent_files;
+        let drop_row = self.drop_row.clone();
+        let accepted_drops = self.accepts_drops();
+
         *self = props;
+
+        // Keep the selection when the parent re-renders
+        self.path = path;
+        self.paths = paths;
+        self.error = error;
+        self.path_text = path_text;
+        self.completion = completion;
+        self.drop_row = drop_row;
+        if self.accepts_drops() != accepted_drops {
+            if let Some(row) = self.drop_row.borrow().as_ref().and_then(WeakRef::upgrade) {
+                set_drop_target(&row, self.accepts_drops());
+            }
+        }
+        // Reading the recent manager is slow, only do it when the list settings change
+        if reload {
+            self.load_recent();
+        } else {
+            self.recent = recent;
+        }
+
         UpdateAction::Render
     }
 
     fn update(&mut self, msg: Self::Message) -> UpdateAction<Self> {
         match msg {
             FileChooserMessage::SetPath { path } => {
+                self.remember(path.iter());
+                self.path_text = path.as_ref().map(|path| path.display().to_string()).unwrap_or_default();
                 self.path = path.clone();
+                self.error = None;
                 self.on_selected.send(path);
                 UpdateAction::Render
             }
+            FileChooserMessage::SetPaths { paths } => {
+                self.remember(&paths);
+                self.paths = paths.clone();
+                self.error = None;
+                self.on_selected_multiple.send(paths);
+                UpdateAction::Render
+            }
+            FileChooserMessage::RemovePath { index } => {
+                if index < self.paths.len() {
+                    self.paths.remove(index);
+                    self.on_selected_multiple.send(self.paths.clone());
+                }
+                UpdateAction::Render
+            }
+            FileChooserMessage::SelectRecent { path } => {
+                if !path.exists() {
+                    // Deleted since the list was loaded, reloading drops it
+                    self.load_recent();
+                    UpdateAction::Render
+                } else if self.select_multiple {
+                    let mut paths = self.paths.clone();
+                    if !paths.contains(&path) {
+                        paths.push(path);
+                    }
+                    self.update(FileChooserMessage::SetPaths { paths })
+                } else {
+                    self.update(FileChooserMessage::SetPath { path: Some(path) })
+                }
+            }
+            FileChooserMessage::EditPath { text } => {
+                // No render, the entry already shows the text
+                self.path_text = text;
+                self.complete_path()
+            }
+            FileChooserMessage::Listed { dir, entries } => {
+                self.completion.listed(dir, entries);
+                // The user may have moved on to another folder in the meantime
+                self.complete_path()
+            }
+            FileChooserMessage::CommitPath => {
+                if self.path_text.trim().is_empty() {
+                    return if self.path.is_some() {
+                        self.update(FileChooserMessage::SetPath { path: None })
+                    } else {
+                        UpdateAction::None
+                    };
+                }
+
+                let path = expand_path(&self.path_text);
+                if self.path.as_ref() == Some(&path) {
+                    return UpdateAction::None;
+                }
+                match self.check_typed_path(&path) {
+                    Ok(()) => self.update(FileChooserMessage::SetPath { path: Some(path) }),
+                    Err(error) => {
+                        self.error = Some(error);
+                        UpdateAction::Render
+                    }
+                }
+            }
+            FileChooserMessage::Reject { .. } => {
+                self.error = Some(self.filter_error_text.clone());
+                UpdateAction::Render
+            }
+            FileChooserMessage::Cancel => {
+                self.on_cancel.send(());
+                UpdateAction::None
+            }
+            FileChooserMessage::Ignore => UpdateAction::None,
         }
     }
 
     fn view(&self) -> VNode<Self> {
-        let dialog_title = self.dialog_title.clone();
-        let dialog_yes_text = self.dialog_yes_text.clone();
-        let dialog_no_text = self.dialog_no_text.clone();
-        let dialog_action = self.dialog_action;
+        let dialog = self.dialog_options();
+        let accept_drops = self.accepts_drops();
+        let drop_row = self.drop_row.clone();
+        let show_path = self.show_path || self.read_only;
+        let editable_path = self.editable_path && !self.read_only;
+        let completion = self.completion.clone();
         gtk! {
-            <Box spacing=self.spacing>
+            <Box orientation=Orientation::Vertical spacing=self.spacing sensitive=self.sensitive
+                property_tooltip_text=self.tooltip.clone()>
+                <Box spacing=self.spacing
+                    on realize=|row| {
+                        drop_row.replace(Some(row.downgrade()));
+                        if accept_drops {
+                            set_drop_target(row, true);
+                        }
+                        FileChooserMessage::Ignore
+                    }
+                    on drag_data_received=|_, _, _, _, data, _, _| dialog.accept_drop(data.get_uris())>
+                    {
+                        gtk_if!(self.label.is_some() => {
+                            <Label label=self.label.clone().unwrap() />
+                        })
+                    }
                 {
-                    gtk_if!(self.label.is_some() => {
-                        <Label label=self.label.clone().unwrap() />
+                    gtk_if!(!self.read_only => {
+                        <Button Box::pack_type=PackType::End label=self.button_label.clone() on clicked=|_| dialog.run() />
                     })
                 }
-                <Button Box::pack_type=PackType::End label=self.button_label.clone() on clicked=|_| {
-                    let dialog = FileChooserNative::new(Some(&dialog_title), vgtk::current_window().as_ref(), dialog_action, Some(&dialog_yes_text), Some(&dialog_no_text));
-                    dialog.run();
-                    FileChooserMessage::SetPath {
-                        path: dialog.get_filename(),
-                    }
-                }/>
-            {
-                gtk_if!(self.show_path && self.path.is_some() => {
-                    <Label Box::pack_type=PackType::End label=self.path.clone().unwrap().display().to_string() ellipsize=EllipsizeMode::Middle />
-                })
+                {
+                    gtk_if!(!self.read_only && !self.recent.is_empty() => {
+                        <Box Box::pack_type=PackType::End>
+                            <@Dropdown items=self.recent.iter().map(|path| path.display().to_string()).collect::<Vec<String>>()
+                                placeholder=Some(self.recent_placeholder.clone())
+                                on_select=|path| match path {
+                                    Some(path) => FileChooserMessage::SelectRecent { path: PathBuf::from(path) },
+                                    None => FileChooserMessage::Ignore,
+                                } />
+                        </Box>
+                    })
+                }
+                {
+                    gtk_if!(show_path && self.select_multiple => {
+                        <Label Box::pack_type=PackType::End label=self.summary() selectable=self.read_only />
+                    })
+                }
+                {
+                    gtk_if!(editable_path && !self.select_multiple => {
+                        <Entry text=self.path_text.clone() Box::pack_type=PackType::End Box::expand=true
+                            on realize=|entry| {
+                                completion.attach(entry);
+                                FileChooserMessage::Ignore
+                            }
+                            on changed=|entry| FileChooserMessage::EditPath { text: entry.get_text().to_string() }
+                            on activate=|_| FileChooserMessage::CommitPath
+                            on focus_out_event=|_, _| FileChooserMessage::CommitPath />
+                    })
+                }
+                {
+                    gtk_if!(show_path && !editable_path && !self.select_multiple && self.path.is_some() => {
+                        <Label Box::pack_type=PackType::End label=self.path.clone().unwrap().display().to_string() ellipsize=EllipsizeMode::Middle
+                            selectable=self.read_only />
+                    })
+                }
+                {
+                    gtk_if!(show_path && !editable_path && !self.select_multiple && self.path.is_none() => {
+                        <Label Box::pack_type=PackType::End label="" ellipsize=EllipsizeMode::Middle />
+                    })
+                }
+                {
+                    gtk_if!(self.error.is_some() => {
+                        <Label Box::pack_type=PackType::End label=self.error.clone().unwrap() />
+                    })
+                }
+                </Box>
+                {
+                    gtk_if!(show_path && self.select_multiple => {
+                        <Box orientation=Orientation::Vertical>
+                        {
+                            self.paths.iter().enumerate().map(|(index, path)| gtk! {
+                                <Box spacing=self.spacing>
+                                    <Label label=path.display().to_string() ellipsize=EllipsizeMode::Middle halign=Align::Start
+                                        selectable=self.read_only />
+                                    {
+                                        gtk_if!(!self.read_only => {
+                                            <Button Box::pack_type=PackType::End label=self.remove_label.clone()
+                                                on clicked=|_| FileChooserMessage::RemovePath { index } />
+                                        })
+                                    }
+                                </Box>
+                            })
+                        }
+                        </Box>
+                    })
+                }
+                { help_view(self.help_text.clone(), self.help_widget.clone()) }
+            </Box>
+        }
+    }
+}
+
+// Everything needed to open the dialog, captured by the button's click handler
+#[derive(Clone, Debug)]
+struct DialogOptions {
+    request: FileDialogRequest,
+    provider: Rc<dyn FileDialogProvider>,
+    default_extension: Option<String>,
+}
+
+impl DialogOptions {
+    fn run(&self) -> FileChooserMessage {
+        let response = self.provider.run(&self.request);
+        self.handle_response(response)
+    }
+
+    fn handle_response(&self, response: FileDialogResponse) -> FileChooserMessage {
+        let (paths, filter) = match response {
+            FileDialogResponse::Cancelled => return FileChooserMessage::Cancel,
+            FileDialogResponse::Selected { paths, filter } => (paths, filter),
+        };
+
+        // The native dialog doesn't always enforce its filter (e.g. typed in names), so paths get
+        // checked against the filter that was active when the dialog closed
+        let active_filter = filter.and_then(|name| self.request.filters.iter().find(|filter| filter.name == name));
+        if self.request.select_multiple {
+            match paths.iter().find(|path| !active_filter.map_or(true, |filter| filter.matches(path))) {
+                Some(path) => FileChooserMessage::Reject { path: path.clone() },
+                None => FileChooserMessage::SetPaths { paths },
             }
-            {
-                gtk_if!(self.show_path && self.path.is_none() => {
-                    <Label Box::pack_type=PackType::End label="" ellipsize=EllipsizeMode::Middle />
-                })
+        } else {
+            match paths.into_iter().next() {
+                Some(path) if self.request.action == FileChooserAction::Save => {
+                    self.complete_save_path(path, active_filter, true)
+                }
+                Some(path) if !active_filter.map_or(true, |filter| filter.matches(&path)) => {
+                    FileChooserMessage::Reject { path }
+                }
+                path => FileChooserMessage::SetPath { path },
             }
-            </Box>
         }
     }
+
+    // Applies the dialog's rules to dropped URIs so drops behave like a selection in the dialog
+    fn accept_drop(&self, uris: Vec<GString>) -> FileChooserMessage {
+        let mut paths: Vec<PathBuf> = uris
+            .iter()
+            .filter_map(|uri| glib::filename_from_uri(uri).ok())
+            .map(|(path, _)| path)
+            .collect();
+        if !self.request.select_multiple {
+            paths.truncate(1);
+        }
+
+        let rejected = paths.iter().find(|path| {
+            let right_kind = match self.request.action {
+                FileChooserAction::SelectFolder | FileChooserAction::CreateFolder => path.is_dir(),
+                FileChooserAction::Save => !path.is_dir(),
+                _ => path.is_file(),
+            };
+            let filters = &self.request.filters;
+            let matches_filter = filters.is_empty() || filters.iter().any(|filter| filter.matches(path));
+            !right_kind || !matches_filter
+        });
+
+        match rejected {
+            Some(path) => FileChooserMessage::Reject { path: path.clone() },
+            None if paths.is_empty() => FileChooserMessage::Ignore,
+            None if self.request.select_multiple => FileChooserMessage::SetPaths { paths },
+            // No dialog asked about replacing the dropped file, declining keeps the selection
+            None if self.request.action == FileChooserAction::Save => {
+                match self.complete_save_path(paths.remove(0), None, false) {
+                    FileChooserMessage::Cancel => FileChooserMessage::Ignore,
+                    msg => msg,
+                }
+            }
+            None => FileChooserMessage::SetPath { path: paths.pop() },
+        }
+    }
+
+    // Adds the active filter's extension when the user left it out and asks before replacing a
+    // file, unless `confirmed` says the dialog already did for this path
+    fn complete_save_path(
+        &self,
+        path: PathBuf,
+        active_filter: Option<&FileFilterSpec>,
+        confirmed: bool,
+    ) -> FileChooserMessage {
+        let (path, confirmed) = if path.extension().is_some() {
+            match active_filter {
+                Some(filter) if !filter.matches(&path) => return FileChooserMessage::Reject { path },
+                _ => (path, confirmed),
+            }
+        } else {
+            let extension = active_filter
+                .and_then(FileFilterSpec::extension)
+                .or_else(|| self.default_extension.clone());
+            match extension {
+                // The dialog only confirmed overwriting the name without the extension
+                Some(extension) => (path.with_extension(extension), false),
+                None => (path, confirmed),
+            }
+        };
+
+        let ask = self.request.confirm_overwrite && !confirmed && path.exists();
+        if ask && !self.provider.confirm_overwrite(&self.request, &path) {
+            return FileChooserMessage::Cancel;
+        }
+        FileChooserMessage::SetPath { path: Some(path) }
+    }
+}
+
+// Lets files be dropped on the row, or stops accepting them
+fn set_drop_target(row: &Box, accept: bool) {
+    if accept {
+        let targets = [TargetEntry::new("text/uri-list", TargetFlags::OTHER_APP, 0)];
+        row.drag_dest_set(DestDefaults::ALL, &targets, DragAction::COPY);
+    } else {
+        row.drag_dest_unset();
+    }
+}
+
+thread_local! {
+    // Lists folders for the path entry's completion so slow folders don't freeze the UI
+    static LISTING_POOL: ThreadPool = ThreadPool::new_exclusive(1).expect("Failed to create folder listing thread");
+}
+
+// Completion popup of the path entry. The typed folder is listed once and the popup is filtered
+// from that listing until the folder part of the text changes.
+#[derive(Debug, Default)]
+struct PathCompletion {
+    completion: RefCell<Option<EntryCompletion>>,
+    // Last folder listed and its entries
+    listing: RefCell<Option<(PathBuf, Vec<FolderEntry>)>>,
+    // Folder being listed on the worker thread
+    pending: RefCell<Option<PathBuf>>,
+}
+
+impl PathCompletion {
+    fn attach(&self, entry: &Entry) {
+        let completion = EntryCompletion::new();
+        completion.set_text_column(0);
+        completion.set_minimum_key_length(1);
+        entry.set_completion(Some(&completion));
+        self.completion.replace(Some(completion));
+    }
+
+    // Refreshes the popup from the cached listing. Returns the folder to list when the typed
+    // folder hasn't been listed yet.
+    fn update(&self, text: &str) -> Option<PathBuf> {
+        let (typed_dir, prefix) = match split_typed_path(text) {
+            Some(split) => split,
+            None => {
+                self.show(vec![]);
+                return None;
+            }
+        };
+        let dir = expand_path(typed_dir);
+        if let Some((listed, entries)) = &*self.listing.borrow() {
+            if *listed == dir {
+                self.show(completions_from(entries, typed_dir, prefix, 200));
+                return None;
+            }
+        }
+        if self.pending.borrow().as_ref() == Some(&dir) {
+            return None;
+        }
+        self.pending.replace(Some(dir.clone()));
+        Some(dir)
+    }
+
+    fn listed(&self, dir: PathBuf, entries: Vec<FolderEntry>) {
+        if self.pending.borrow().as_ref() == Some(&dir) {
+            self.pending.replace(None);
+        }
+        self.listing.replace(Some((dir, entries)));
+    }
+
+    fn show(&self, completions: Vec<String>) {
+        if let Some(completion) = &*self.completion.borrow() {
+            let model = ListStore::new(&[Type::String]);
+            for path in completions {
+                model.insert_with_values(None, &[0], &[&path]);
+            }
+            completion.set_model(Some(&model));
+        }
+    }
+}
+
+// Matches a file name against a glob pattern supporting `*` and `?`
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name position it was tried at
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            // Let the last `*` swallow one more character
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::fs::File;
+
+    use super::*;
+    use crate::file_dialog::ScriptedFileDialog;
+
+    #[derive(Default)]
+    struct Sent {
+        selected: RefCell<Vec<Option<PathBuf>>>,
+        selected_multiple: RefCell<Vec<Vec<PathBuf>>>,
+        cancelled: Cell<usize>,
+    }
+
+    // Opens the dialog once, like clicking the button, and handles the message it returns
+    fn run_dialog(props: FileChooser, dialog: ScriptedFileDialog) -> (FileChooserMessage, Rc<Sent>, Rc<ScriptedFileDialog>) {
+        let sent = Rc::new(Sent::default());
+        let dialog = Rc::new(dialog);
+        let (selected, selected_multiple, cancelled) = (sent.clone(), sent.clone(), sent.clone());
+        let mut chooser = FileChooser::create(FileChooser {
+            dialog_provider: dialog.clone(),
+            on_selected: Callback::from(move |path: Option<PathBuf>| selected.selected.borrow_mut().push(path)),
+            on_selected_multiple: Callback::from(move |paths: Vec<PathBuf>| {
+                selected_multiple.selected_multiple.borrow_mut().push(paths)
+            }),
+            on_cancel: Callback::from(move |_: ()| cancelled.cancelled.set(cancelled.cancelled.get() + 1)),
+            ..props
+        });
+
+        let msg = chooser.open_dialog();
+        chooser.update(msg.clone());
+        (msg, sent, dialog)
+    }
+
+    #[test]
+    fn pick_selects_the_path() {
+        let props = FileChooser {
+            dialog_title: String::from("Open report"),
+            ..FileChooser::default()
+        };
+        let (msg, sent, dialog) = run_dialog(props, ScriptedFileDialog::new().pick("/tmp/report.txt"));
+
+        assert!(matches!(msg, FileChooserMessage::SetPath { .. }));
+        assert_eq!(*sent.selected.borrow(), vec![Some(PathBuf::from("/tmp/report.txt"))]);
+        assert_eq!(sent.cancelled.get(), 0);
+        assert_eq!(dialog.requests()[0].title, "Open report");
+    }
+
+    #[test]
+    fn cancel_keeps_the_selection() {
+        let (msg, sent, _) = run_dialog(FileChooser::default(), ScriptedFileDialog::new().cancel());
+
+        assert!(matches!(msg, FileChooserMessage::Cancel));
+        assert!(sent.selected.borrow().is_empty());
+        assert_eq!(sent.cancelled.get(), 1);
+    }
+
+    #[test]
+    fn pick_many_selects_all_paths() {
+        let props = FileChooser {
+            select_multiple: true,
+            ..FileChooser::default()
+        };
+        let paths = vec![PathBuf::from("/tmp/a.txt"), PathBuf::from("/tmp/b.txt")];
+        let (_, sent, dialog) = run_dialog(props, ScriptedFileDialog::new().pick_many(paths.clone()));
+
+        assert_eq!(*sent.selected_multiple.borrow(), vec![paths]);
+        assert!(sent.selected.borrow().is_empty());
+        assert!(dialog.requests()[0].select_multiple);
+    }
+
+    #[test]
+    fn pick_outside_the_filter_is_rejected() {
+        let props = FileChooser {
+            filters: vec![FileFilterSpec::new("CSV files").pattern("*.csv")],
+            ..FileChooser::default()
+        };
+        let dialog = ScriptedFileDialog::new().pick_with_filter(vec![PathBuf::from("/tmp/notes.txt")], "CSV files");
+        let (msg, sent, _) = run_dialog(props, dialog);
+
+        assert!(matches!(msg, FileChooserMessage::Reject { .. }));
+        assert!(sent.selected.borrow().is_empty());
+        assert_eq!(sent.cancelled.get(), 0);
+    }
+
+    #[test]
+    fn declining_overwrite_cancels() {
+        let existing = std::env::temp_dir().join("vgtk_components_overwrite_test.csv");
+        File::create(&existing).unwrap();
+        let props = FileChooser {
+            dialog_action: FileChooserAction::Save,
+            default_extension: Some(String::from("csv")),
+            ..FileChooser::default()
+        };
+        // The dialog only sees the name without the extension
+        let dialog = ScriptedFileDialog::new().pick(existing.with_extension("")).answer_overwrite(false);
+        let (msg, sent, _) = run_dialog(props, dialog);
+        std::fs::remove_file(&existing).unwrap();
+
+        assert!(matches!(msg, FileChooserMessage::Cancel));
+        assert!(sent.selected.borrow().is_empty());
+        assert_eq!(sent.cancelled.get(), 1);
+    }
+
+    #[test]
+    fn accepting_overwrite_adds_the_extension() {
+        let existing = std::env::temp_dir().join("vgtk_components_replace_test.csv");
+        File::create(&existing).unwrap();
+        let props = FileChooser {
+            dialog_action: FileChooserAction::Save,
+            default_extension: Some(String::from("csv")),
+            ..FileChooser::default()
+        };
+        let dialog = ScriptedFileDialog::new().pick(existing.with_extension("")).answer_overwrite(true);
+        let (_, sent, _) = run_dialog(props, dialog);
+        std::fs::remove_file(&existing).unwrap();
+
+        assert_eq!(*sent.selected.borrow(), vec![Some(existing)]);
+    }
+
+    #[test]
+    fn dropping_an_existing_file_in_save_mode_asks_first() {
+        let existing = std::env::temp_dir().join("vgtk_components_drop_test.csv");
+        File::create(&existing).unwrap();
+        let uri = glib::filename_to_uri(&existing, None).unwrap();
+        let chooser = |dialog: ScriptedFileDialog| {
+            FileChooser {
+                dialog_action: FileChooserAction::Save,
+                dialog_provider: Rc::new(dialog),
+                ..FileChooser::default()
+            }
+            .dialog_options()
+        };
+
+        let declined = chooser(ScriptedFileDialog::new().answer_overwrite(false)).accept_drop(vec![uri.clone()]);
+        let accepted = chooser(ScriptedFileDialog::new().answer_overwrite(true)).accept_drop(vec![uri]);
+        std::fs::remove_file(&existing).unwrap();
+
+        assert!(matches!(declined, FileChooserMessage::Ignore));
+        assert!(matches!(accepted, FileChooserMessage::SetPath { path: Some(path) } if path == existing));
+    }
+
+    #[test]
+    fn dropping_a_new_name_in_save_mode_adds_the_extension() {
+        let new = std::env::temp_dir().join("vgtk_components_drop_missing_test");
+        let uri = glib::filename_to_uri(&new, None).unwrap();
+        let options = FileChooser {
+            dialog_action: FileChooserAction::Save,
+            default_extension: Some(String::from("csv")),
+            ..FileChooser::default()
+        }
+        .dialog_options();
+
+        let msg = options.accept_drop(vec![uri]);
+        assert!(matches!(msg, FileChooserMessage::SetPath { path: Some(path) } if path == new.with_extension("csv")));
+    }
 }
@@ -0,0 +1,181 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use vgtk::lib::gtk::prelude::*;
+use vgtk::lib::gtk::*;
+
+use crate::file_chooser::FileFilterSpec;
+
+/// Everything `FileChooser` asks of the dialog.
+#[derive(Clone, Debug)]
+pub struct FileDialogRequest {
+    pub title: String,
+    pub yes_text: String,
+    pub no_text: String,
+    pub action: FileChooserAction,
+    pub select_multiple: bool,
+    pub filters: Vec<FileFilterSpec>,
+    // Label of the "All files" filter, if offered
+    pub all_files_label: Option<String>,
+    // Folder to open, or a file to select (save mode: whose name to suggest)
+    pub initial_path: Option<PathBuf>,
+    pub suggested_name: Option<String>,
+    pub confirm_overwrite: bool,
+    // Question asked by `FileDialogProvider::confirm_overwrite`, `{path}` is replaced with the path
+    pub overwrite_text: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FileDialogResponse {
+    Cancelled,
+    Selected {
+        paths: Vec<PathBuf>,
+        // Name of the filter that was active when the dialog closed
+        filter: Option<String>,
+    },
+}
+
+/// Shows the file dialog for a `FileChooser`. `NativeFileDialog` is used by default; headless
+/// tests can swap in a `ScriptedFileDialog`.
+pub trait FileDialogProvider: fmt::Debug {
+    /// Shows the dialog and blocks until the user is done with it.
+    fn run(&self, request: &FileDialogRequest) -> FileDialogResponse;
+
+    /// Asks whether an existing file may be replaced. Only called for save paths the dialog
+    /// itself didn't confirm, e.g. after a default extension was added.
+    fn confirm_overwrite(&self, request: &FileDialogRequest, path: &Path) -> bool;
+}
+
+/// Runs a `FileChooserNative`, needs a desktop session.
+#[derive(Clone, Debug, Default)]
+pub struct NativeFileDialog;
+
+impl FileDialogProvider for NativeFileDialog {
+    fn run(&self, request: &FileDialogRequest) -> FileDialogResponse {
+        let dialog = FileChooserNative::new(Some(&request.title), vgtk::current_window().as_ref(), request.action, Some(&request.yes_text), Some(&request.no_text));
+        dialog.set_select_multiple(request.select_multiple);
+        for filter in &request.filters {
+            dialog.add_filter(&filter.to_file_filter());
+        }
+        if let Some(all_files_label) = &request.all_files_label {
+            dialog.add_filter(&FileFilterSpec::new(all_files_label).pattern("*").to_file_filter());
+        }
+        let save = request.action == FileChooserAction::Save;
+        match &request.initial_path {
+            Some(path) if path.is_dir() => {
+                dialog.set_current_folder(path);
+            }
+            // `set_filename` only works for existing files, a new file name is suggested instead
+            Some(path) if save => {
+                if let Some(folder) = path.parent().filter(|folder| folder.is_dir()) {
+                    dialog.set_current_folder(folder);
+                }
+                if let Some(name) = path.file_name() {
+                    dialog.set_current_name(&name.to_string_lossy());
+                }
+            }
+            Some(path) => {
+                dialog.set_filename(path);
+            }
+            None => {}
+        }
+        if save {
+            if let Some(suggested_name) = &request.suggested_name {
+                dialog.set_current_name(suggested_name);
+            }
+            dialog.set_do_overwrite_confirmation(request.confirm_overwrite);
+        }
+
+        if dialog.run() != ResponseType::Accept {
+            return FileDialogResponse::Cancelled;
+        }
+
+        let paths = if request.select_multiple {
+            dialog.get_filenames()
+        } else {
+            dialog.get_filename().into_iter().collect()
+        };
+        let filter = dialog
+            .get_filter()
+            .and_then(|filter| filter.get_name())
+            .map(|name| name.to_string());
+        FileDialogResponse::Selected { paths, filter }
+    }
+
+    fn confirm_overwrite(&self, request: &FileDialogRequest, path: &Path) -> bool {
+        let message = request.overwrite_text.replace("{path}", &path.display().to_string());
+        let dialog = MessageDialog::new(vgtk::current_window().as_ref(), DialogFlags::MODAL, MessageType::Question, ButtonsType::YesNo, &message);
+        let response = dialog.run();
+        dialog.destroy();
+        response == ResponseType::Yes
+    }
+}
+
+/// Plays back queued responses instead of showing a dialog, and records the requests it got.
+/// Once the queue runs out every dialog is cancelled.
+#[derive(Debug, Default)]
+pub struct ScriptedFileDialog {
+    responses: RefCell<VecDeque<FileDialogResponse>>,
+    overwrite_answers: RefCell<VecDeque<bool>>,
+    requests: RefCell<Vec<FileDialogRequest>>,
+}
+
+impl ScriptedFileDialog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues picking a single path with no filter active.
+    pub fn pick<P: Into<PathBuf>>(self, path: P) -> Self {
+        self.pick_many(vec![path.into()])
+    }
+
+    /// Queues picking several paths with no filter active.
+    pub fn pick_many(self, paths: Vec<PathBuf>) -> Self {
+        self.respond(FileDialogResponse::Selected { paths, filter: None })
+    }
+
+    /// Queues picking paths while the named filter is active.
+    pub fn pick_with_filter(self, paths: Vec<PathBuf>, filter: &str) -> Self {
+        self.respond(FileDialogResponse::Selected {
+            paths,
+            filter: Some(filter.to_string()),
+        })
+    }
+
+    pub fn cancel(self) -> Self {
+        self.respond(FileDialogResponse::Cancelled)
+    }
+
+    pub fn respond(self, response: FileDialogResponse) -> Self {
+        self.responses.borrow_mut().push_back(response);
+        self
+    }
+
+    /// Queues the answer to the next overwrite confirmation, which defaults to yes.
+    pub fn answer_overwrite(self, replace: bool) -> Self {
+        self.overwrite_answers.borrow_mut().push_back(replace);
+        self
+    }
+
+    /// Requests received so far, oldest first.
+    pub fn requests(&self) -> Vec<FileDialogRequest> {
+        self.requests.borrow().clone()
+    }
+}
+
+impl FileDialogProvider for ScriptedFileDialog {
+    fn run(&self, request: &FileDialogRequest) -> FileDialogResponse {
+        self.requests.borrow_mut().push(request.clone());
+        self.responses
+            .borrow_mut()
+            .pop_front()
+            .unwrap_or(FileDialogResponse::Cancelled)
+    }
+
+    fn confirm_overwrite(&self, _request: &FileDialogRequest, _path: &Path) -> bool {
+        self.overwrite_answers.borrow_mut().pop_front().unwrap_or(true)
+    }
+}
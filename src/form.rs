@@ -0,0 +1,681 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
+use vgtk::lib::gtk::prelude::*;
+use vgtk::lib::gtk::*;
+use vgtk::{gtk, gtk_if, Callback, Component, UpdateAction, VNode};
+
+use crate::checkbox::{CheckBox, CheckState};
+use crate::date::{Date, DateInput};
+use crate::dropdown::Dropdown;
+use crate::passwordbox::PasswordBox;
+use crate::phone_number::PhoneNumber;
+use crate::switch::Switch;
+use crate::textbox::TextBox;
+
+/// Value of a single form field.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde_crate::Serialize, serde_crate::Deserialize), serde(crate = "serde_crate"))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value", rename_all = "lowercase"))]
+pub enum FieldValue {
+    Text(String),
+    Bool(bool),
+    Date(Date),
+    Choice(
+        // Saved as an empty string when nothing is selected, TOML has no null
+        #[cfg_attr(feature = "serde", serde(with = "crate::drafts::empty_as_none"))]
+        Option<String>,
+    ),
+}
+
+impl FieldValue {
+    /// Whether a required field still needs a value. A required `Bool` has to be checked, e.g. to
+    /// accept terms.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            FieldValue::Text(text) => text.trim().is_empty(),
+            FieldValue::Bool(value) => !value,
+            FieldValue::Date(date) => date.month.is_empty() || date.day.is_empty() || date.year.is_empty(),
+            FieldValue::Choice(choice) => choice.is_none(),
+        }
+    }
+}
+
+/// Which component edits a field.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldKind {
+    Text,
+    Password,
+    Phone,
+    CheckBox,
+    Switch,
+    Date,
+    Dropdown { items: Vec<String> },
+}
+
+impl FieldKind {
+    /// Whether the kind's component can show the value.
+    pub fn accepts(&self, value: &FieldValue) -> bool {
+        matches!(
+            (self, value),
+            (FieldKind::Text, FieldValue::Text(_))
+                | (FieldKind::Password, FieldValue::Text(_))
+                | (FieldKind::Phone, FieldValue::Text(_))
+                | (FieldKind::CheckBox, FieldValue::Bool(_))
+                | (FieldKind::Switch, FieldValue::Bool(_))
+                | (FieldKind::Date, FieldValue::Date(_))
+                | (FieldKind::Dropdown { .. }, FieldValue::Choice(_))
+        )
+    }
+}
+
+/// Checks a field's value, returning the message to show when it's invalid.
+#[derive(Clone)]
+pub struct Validator(Rc<dyn Fn(&FieldValue) -> Result<(), String>>);
+
+impl fmt::Debug for Validator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Validator").finish()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FormField {
+    // Identifies the field in `FormValues`
+    pub key: String,
+    pub label: String,
+    pub kind: FieldKind,
+    pub initial: FieldValue,
+    pub required: bool,
+    pub validators: Vec<Validator>,
+    pub help_text: Option<String>,
+    // Only used by text, password and phone fields
+    pub placeholder: Option<String>,
+    pub tooltip: Option<String>,
+}
+
+impl FormField {
+    pub fn new(key: &str, label: &str, kind: FieldKind, initial: FieldValue) -> Self {
+        Self {
+            key: key.to_string(),
+            label: label.to_string(),
+            kind,
+            initial,
+            required: false,
+            validators: vec![],
+            help_text: None,
+            placeholder: None,
+            tooltip: None,
+        }
+    }
+
+    pub fn text(key: &str, label: &str) -> Self {
+        Self::new(key, label, FieldKind::Text, FieldValue::Text(String::new()))
+    }
+
+    pub fn password(key: &str, label: &str) -> Self {
+        Self::new(key, label, FieldKind::Password, FieldValue::Text(String::new()))
+    }
+
+    pub fn phone(key: &str, label: &str) -> Self {
+        Self::new(key, label, FieldKind::Phone, FieldValue::Text(String::new()))
+    }
+
+    pub fn checkbox(key: &str, label: &str) -> Self {
+        Self::new(key, label, FieldKind::CheckBox, FieldValue::Bool(false))
+    }
+
+    pub fn switch(key: &str, label: &str) -> Self {
+        Self::new(key, label, FieldKind::Switch, FieldValue::Bool(false))
+    }
+
+    pub fn date(key: &str, label: &str) -> Self {
+        Self::new(key, label, FieldKind::Date, FieldValue::Date(Date::default()))
+    }
+
+    pub fn dropdown(key: &str, label: &str, items: Vec<String>) -> Self {
+        Self::new(key, label, FieldKind::Dropdown { items }, FieldValue::Choice(None))
+    }
+
+    pub fn initial(mut self, initial: FieldValue) -> Self {
+        self.initial = initial;
+        self
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn help(mut self, help_text: &str) -> Self {
+        self.help_text = Some(help_text.to_string());
+        self
+    }
+
+    pub fn placeholder(mut self, placeholder: &str) -> Self {
+        self.placeholder = Some(placeholder.to_string());
+        self
+    }
+
+    pub fn tooltip(mut self, tooltip: &str) -> Self {
+        self.tooltip = Some(tooltip.to_string());
+        self
+    }
+
+    pub fn validate<F>(mut self, validator: F) -> Self
+    where
+        F: Fn(&FieldValue) -> Result<(), String> + 'static,
+    {
+        self.validators.push(Validator(Rc::new(validator)));
+        self
+    }
+
+    /// Whether both fields edit the same value the same way, ignoring labels and validators.
+    pub fn same_value(&self, other: &FormField) -> bool {
+        self.key == other.key && self.kind == other.kind && self.initial == other.initial
+    }
+
+    /// The first problem with the value, if any.
+    pub fn check(&self, value: &FieldValue, required_text: &str) -> Result<(), String> {
+        if value.is_empty() {
+            return if self.required { Err(required_text.to_string()) } else { Ok(()) };
+        }
+        self.validators.iter().try_for_each(|validator| (validator.0)(value))
+    }
+}
+
+/// Values of all fields of a `Form`, by key.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde_crate::Serialize, serde_crate::Deserialize), serde(crate = "serde_crate", transparent))]
+pub struct FormValues(BTreeMap<String, FieldValue>);
+
+impl FormValues {
+    pub fn get(&self, key: &str) -> Option<&FieldValue> {
+        self.0.get(key)
+    }
+
+    pub fn set(&mut self, key: &str, value: FieldValue) {
+        self.0.insert(key.to_string(), value);
+    }
+
+    pub fn text(&self, key: &str) -> Option<&str> {
+        match self.get(key) {
+            Some(FieldValue::Text(text)) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn bool(&self, key: &str) -> Option<bool> {
+        match self.get(key) {
+            Some(FieldValue::Bool(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn date(&self, key: &str) -> Option<&Date> {
+        match self.get(key) {
+            Some(FieldValue::Date(date)) => Some(date),
+            _ => None,
+        }
+    }
+
+    pub fn choice(&self, key: &str) -> Option<&str> {
+        match self.get(key) {
+            Some(FieldValue::Choice(choice)) => choice.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &FieldValue)> {
+        self.0.iter()
+    }
+}
+
+/// Renders its fields with the crate's components and keeps track of their values, which fields
+/// were changed or touched, and whether the whole form is valid.
+#[derive(Clone, Debug)]
+pub struct Form {
+    pub fields: Vec<FormField>,
+    // Values to start from instead of the fields' initial ones, e.g. a saved draft. Fields keep
+    // comparing against their initial values, so drafted fields show as changed.
+    pub draft: Option<FormValues>,
+    pub submit_label: String,
+    pub required_text: String,
+    // Keeps the submit button disabled until something differs from the initial values. Invalid
+    // forms can still be submitted, which shows the errors of all fields instead.
+    pub submit_requires_changes: bool,
+    // Lists all errors of touched fields above the submit button
+    pub show_error_summary: bool,
+    pub spacing: i32,
+    pub widget_name: String,
+    pub error_widget: String,
+    pub summary_widget: String,
+    pub on_changed: Callback<FormValues>,
+    pub on_submit: Callback<FormValues>,
+    values: FormValues,
+    touched: HashSet<String>,
+    prop_draft: Option<FormValues>,
+}
+
+impl Default for Form {
+    fn default() -> Self {
+        Self {
+            fields: vec![],
+            draft: None,
+            submit_label: String::from("Submit"),
+            required_text: String::from("This field is required"),
+            submit_requires_changes: false,
+            show_error_summary: true,
+            spacing: 10,
+            widget_name: String::from("form"),
+            error_widget: String::from("error"),
+            summary_widget: String::from("error-summary"),
+            on_changed: Callback::default(),
+            on_submit: Callback::default(),
+            values: FormValues::default(),
+            touched: HashSet::new(),
+            prop_draft: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum FormMessage {
+    SetValue { key: String, value: FieldValue },
+    Submit,
+}
+
+impl Form {
+    pub fn values(&self) -> &FormValues {
+        &self.values
+    }
+
+    fn value(&self, field: &FormField) -> FieldValue {
+        self.values.get(&field.key).cloned().unwrap_or_else(|| field.initial.clone())
+    }
+
+    /// Whether the field differs from its initial value.
+    pub fn is_dirty(&self, key: &str) -> bool {
+        self.fields
+            .iter()
+            .find(|field| field.key == key)
+            .map_or(false, |field| self.value(field) != field.initial)
+    }
+
+    /// Whether the user has changed the field at some point, even if it's back at its initial
+    /// value now.
+    pub fn is_touched(&self, key: &str) -> bool {
+        self.touched.contains(key)
+    }
+
+    pub fn error(&self, key: &str) -> Option<String> {
+        let field = self.fields.iter().find(|field| field.key == key)?;
+        field.check(&self.value(field), &self.required_text).err()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.fields.iter().all(|field| self.error(&field.key).is_none())
+    }
+
+    /// Whether submitting would send `on_submit`.
+    pub fn can_submit(&self) -> bool {
+        self.is_valid() && self.submit_enabled()
+    }
+
+    // The button stays clickable on invalid forms, submitting is how untouched fields get to
+    // show their errors
+    fn submit_enabled(&self) -> bool {
+        !self.submit_requires_changes || self.fields.iter().any(|field| self.is_dirty(&field.key))
+    }
+
+    // Errors are only shown once a field was touched, an untouched form isn't full of them
+    fn shown_errors(&self) -> Vec<(String, String)> {
+        self.fields
+            .iter()
+            .filter(|field| self.is_touched(&field.key))
+            .filter_map(|field| self.error(&field.key).map(|error| (field.label.clone(), error)))
+            .collect()
+    }
+
+    // Values for every field, with fields that were added since keeping their initial value
+    fn fill_values(&mut self) {
+        let mut values = FormValues::default();
+        for field in &self.fields {
+            values.set(&field.key, self.value(field));
+        }
+        self.values = values;
+        let fields = &self.fields;
+        self.touched.retain(|key| fields.iter().any(|field| &field.key == key));
+    }
+
+    // Only for fields the form has, a draft of an older version of the form may have others or
+    // have stored a field with another kind
+    fn apply_draft(&mut self) {
+        if let Some(draft) = &self.draft {
+            for field in &self.fields {
+                if let Some(value) = draft.get(&field.key).filter(|value| field.kind.accepts(value)) {
+                    self.values.set(&field.key, value.clone());
+                }
+            }
+        }
+        self.prop_draft = self.draft.clone();
+    }
+
+    fn error_view(&self, error: Option<String>) -> VNode<Self> {
+        gtk! {
+            <Box orientation=Orientation::Horizontal>
+                {
+                    gtk_if!(error.is_some() => {
+                        <Label label=error.clone().unwrap_or_default() widget_name=self.error_widget.clone()
+                            halign=Align::Start />
+                    })
+                }
+            </Box>
+        }
+    }
+
+    // Inputs show the error and required marker themselves, check boxes and switches get an
+    // error line added here
+    fn field_view(&self, field: &FormField, error: Option<String>) -> VNode<Self> {
+        let key = field.key.clone();
+        let label = Some(field.label.clone());
+        let required = field.required;
+        let help_text = field.help_text.clone();
+        let placeholder = field.placeholder.clone();
+        let tooltip = field.tooltip.clone();
+        match (&field.kind, self.value(field)) {
+            (FieldKind::Password, FieldValue::Text(text)) => gtk! {
+                <@PasswordBox label=label text=text required=required error=error
+                    help_text=help_text placeholder=placeholder tooltip=tooltip
+                    on_changed=|value| FormMessage::SetValue { key: key.clone(), value: FieldValue::Text(value) } />
+            },
+            (FieldKind::Phone, FieldValue::Text(text)) => gtk! {
+                <@PhoneNumber label=label text=text required=required error=error
+                    help_text=help_text placeholder=placeholder tooltip=tooltip
+                    on_changed=|value| FormMessage::SetValue { key: key.clone(), value: FieldValue::Text(value) } />
+            },
+            (FieldKind::CheckBox, FieldValue::Bool(checked)) => gtk! {
+                <Box orientation=Orientation::Vertical spacing=4>
+                    <@CheckBox label=label state=CheckState::from(checked) help_text=help_text tooltip=tooltip
+                        on_toggled=|state| FormMessage::SetValue { key: key.clone(), value: FieldValue::Bool(state.is_checked()) } />
+                    { self.error_view(error) }
+                </Box>
+            },
+            (FieldKind::Switch, FieldValue::Bool(active)) => gtk! {
+                <Box orientation=Orientation::Vertical spacing=4>
+                    <@Switch label=label active=active help_text=help_text tooltip=tooltip
+                        on_toggled=|active| FormMessage::SetValue { key: key.clone(), value: FieldValue::Bool(active) } />
+                    { self.error_view(error) }
+                </Box>
+            },
+            (FieldKind::Date, FieldValue::Date(date)) => gtk! {
+                <@DateInput label=label date=date required=required error=error help_text=help_text
+                    tooltip=tooltip
+                    on_update=|date| FormMessage::SetValue { key: key.clone(), value: FieldValue::Date(date) } />
+            },
+            (FieldKind::Dropdown { items }, FieldValue::Choice(selected)) => gtk! {
+                <@Dropdown label=label items=items.clone() selected=selected required=required error=error
+                    help_text=help_text tooltip=tooltip
+                    on_select=|value| FormMessage::SetValue { key: key.clone(), value: FieldValue::Choice(value) } />
+            },
+            (_, value) => {
+                // Text fields, and any field whose initial value doesn't fit its kind
+                let text = match value {
+                    FieldValue::Text(text) => text,
+                    _ => String::new(),
+                };
+                gtk! {
+                    <@TextBox label=label text=text required=required error=error
+                        help_text=help_text placeholder=placeholder tooltip=tooltip
+                        on_changed=|value| FormMessage::SetValue { key: key.clone(), value: FieldValue::Text(value) } />
+                }
+            }
+        }
+    }
+}
+
+/// Whether two field lists edit the same values, see `FormField::same_value`.
+pub fn same_fields(fields: &[FormField], other: &[FormField]) -> bool {
+    fields.len() == other.len() && fields.iter().zip(other).all(|(field, other)| field.same_value(other))
+}
+
+impl Component for Form {
+    type Message = FormMessage;
+    type Properties = Self;
+
+    fn create(props: Self::Properties) -> Self {
+        let mut component = props;
+        component.fill_values();
+        component.apply_draft();
+        component
+    }
+
+    fn change(&mut self, props: Self::Properties) -> UpdateAction<Self> {
+        let values = std::mem::take(&mut self.values);
+        let touched = std::mem::take(&mut self.touched);
+        let prop_draft = self.prop_draft.take();
+        let same_fields = same_fields(&self.fields, &props.fields);
+
+        *self = props;
+        self.prop_draft = prop_draft;
+        // Edits only carry over while the fields stay the same, new initial values (e.g. another
+        // record loaded by the parent) start the form over
+        if same_fields {
+            self.values = values;
+            self.touched = touched;
+            self.fill_values();
+            if self.draft != self.prop_draft {
+                self.apply_draft();
+            }
+        } else {
+            self.fill_values();
+            self.apply_draft();
+        }
+
+        UpdateAction::Render
+    }
+
+    fn update(&mut self, msg: Self::Message) -> UpdateAction<Self> {
+        match msg {
+            FormMessage::SetValue { key, value } => {
+                if self.values.get(&key) == Some(&value) {
+                    return UpdateAction::None;
+                }
+                self.values.set(&key, value);
+                self.touched.insert(key);
+                self.on_changed.send(self.values.clone());
+                UpdateAction::Render
+            }
+            FormMessage::Submit => {
+                // Submitting shows the errors of every field
+                self.touched = self.fields.iter().map(|field| field.key.clone()).collect();
+                if self.can_submit() {
+                    self.on_submit.send(self.values.clone());
+                }
+                UpdateAction::Render
+            }
+        }
+    }
+
+    fn view(&self) -> VNode<Self> {
+        let errors = self.shown_errors();
+        gtk! {
+            <Box orientation=Orientation::Vertical spacing=self.spacing widget_name=self.widget_name.clone()>
+                {
+                    self.fields.iter().map(|field| {
+                        let error = self.error(&field.key).filter(|_| self.is_touched(&field.key));
+                        self.field_view(field, error)
+                    })
+                }
+                {
+                    gtk_if!(self.show_error_summary && !errors.is_empty() => {
+                        <Box orientation=Orientation::Vertical spacing=4 widget_name=self.summary_widget.clone()>
+                            {
+                                errors.iter().map(|(label, error)| gtk! {
+                                    <Label label=format!("{} {}", label, error) halign=Align::Start />
+                                })
+                            }
+                        </Box>
+                    })
+                }
+                <Box orientation=Orientation::Horizontal>
+                    <Button label=self.submit_label.clone() sensitive=self.submit_enabled() Box::pack_type=PackType::End
+                        on clicked=|_| FormMessage::Submit />
+                </Box>
+            </Box>
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    fn text(value: &str) -> FieldValue {
+        FieldValue::Text(value.to_string())
+    }
+
+    fn props(fields: Vec<FormField>, submitted: &Rc<RefCell<Vec<FormValues>>>) -> Form {
+        let submitted = submitted.clone();
+        Form {
+            fields,
+            on_submit: Callback::from(move |values: FormValues| submitted.borrow_mut().push(values)),
+            ..Form::default()
+        }
+    }
+
+    fn set_value(form: &mut Form, key: &str, value: FieldValue) {
+        form.update(FormMessage::SetValue { key: key.to_string(), value });
+    }
+
+    #[test]
+    fn kinds_accept_their_values() {
+        assert!(FieldKind::Text.accepts(&text("a")));
+        assert!(FieldKind::Phone.accepts(&text("")));
+        assert!(FieldKind::Switch.accepts(&FieldValue::Bool(true)));
+        assert!(FieldKind::Date.accepts(&FieldValue::Date(Date::default())));
+        assert!(FieldKind::Dropdown { items: vec![] }.accepts(&FieldValue::Choice(None)));
+        assert!(!FieldKind::Text.accepts(&FieldValue::Bool(true)));
+        assert!(!FieldKind::CheckBox.accepts(&text("true")));
+        assert!(!FieldKind::Dropdown { items: vec![] }.accepts(&text("alpha")));
+    }
+
+    #[test]
+    fn check_requires_values_before_validating() {
+        let field = FormField::text("name", "Name")
+            .required()
+            .validate(|value| match value {
+                FieldValue::Text(text) if text.len() < 3 => Err(String::from("Too short")),
+                _ => Ok(()),
+            });
+        assert_eq!(field.check(&text("  "), "Required"), Err(String::from("Required")));
+        assert_eq!(field.check(&text("ab"), "Required"), Err(String::from("Too short")));
+        assert_eq!(field.check(&text("abc"), "Required"), Ok(()));
+
+        // Optional fields skip the validators while empty
+        let field = FormField::text("name", "Name").validate(|_| Err(String::from("Never valid")));
+        assert_eq!(field.check(&text(""), "Required"), Ok(()));
+        let terms = FormField::checkbox("terms", "Terms").required();
+        assert_eq!(terms.check(&FieldValue::Bool(false), "Required"), Err(String::from("Required")));
+    }
+
+    #[test]
+    fn editing_marks_fields_dirty_and_touched() {
+        let submitted = Rc::new(RefCell::new(vec![]));
+        let fields = vec![FormField::text("name", "Name"), FormField::switch("news", "News")];
+        let mut form = Form::create(props(fields, &submitted));
+        assert!(!form.is_dirty("name"));
+        assert!(!form.is_touched("name"));
+
+        set_value(&mut form, "name", text("Ada"));
+        assert!(form.is_dirty("name"));
+        assert!(form.is_touched("name"));
+        assert!(!form.is_touched("news"));
+
+        // Going back to the initial value is clean again, but stays touched
+        set_value(&mut form, "name", text(""));
+        assert!(!form.is_dirty("name"));
+        assert!(form.is_touched("name"));
+        assert!(!form.is_dirty("missing"));
+    }
+
+    #[test]
+    fn submit_touches_all_fields_and_sends_only_valid_forms() {
+        let submitted = Rc::new(RefCell::new(vec![]));
+        let fields = vec![FormField::text("name", "Name").required(), FormField::switch("news", "News")];
+        let mut form = Form::create(props(fields, &submitted));
+        assert!(!form.can_submit());
+
+        form.update(FormMessage::Submit);
+        assert!(form.is_touched("name"));
+        assert!(form.is_touched("news"));
+        assert_eq!(form.error("name"), Some(form.required_text.clone()));
+        assert!(submitted.borrow().is_empty());
+
+        set_value(&mut form, "name", text("Ada"));
+        assert!(form.can_submit());
+        form.update(FormMessage::Submit);
+        assert_eq!(submitted.borrow().len(), 1);
+        assert_eq!(submitted.borrow()[0].text("name"), Some("Ada"));
+        assert_eq!(submitted.borrow()[0].bool("news"), Some(false));
+    }
+
+    #[test]
+    fn submit_can_require_changes() {
+        let submitted = Rc::new(RefCell::new(vec![]));
+        let mut form = Form::create(Form {
+            submit_requires_changes: true,
+            ..props(vec![FormField::text("name", "Name").initial(text("Ada"))], &submitted)
+        });
+        assert!(form.is_valid());
+        assert!(!form.submit_enabled());
+        assert!(!form.can_submit());
+
+        set_value(&mut form, "name", text("Grace"));
+        assert!(form.can_submit());
+    }
+
+    #[test]
+    fn drafts_fill_matching_fields() {
+        let submitted = Rc::new(RefCell::new(vec![]));
+        let mut draft = FormValues::default();
+        draft.set("name", text("Ada"));
+        draft.set("removed", text("old"));
+        let form = Form::create(Form {
+            draft: Some(draft),
+            ..props(vec![FormField::text("name", "Name"), FormField::switch("news", "News")], &submitted)
+        });
+
+        assert_eq!(form.values().text("name"), Some("Ada"));
+        assert_eq!(form.values().bool("news"), Some(false));
+        assert_eq!(form.values().get("removed"), None);
+        // Drafted values differ from the initial ones but weren't touched by the user
+        assert!(form.is_dirty("name"));
+        assert!(!form.is_touched("name"));
+    }
+
+    #[test]
+    fn drafts_skip_values_of_another_kind() {
+        let submitted = Rc::new(RefCell::new(vec![]));
+        let mut draft = FormValues::default();
+        // Saved before "news" was turned from a text field into a switch
+        draft.set("news", text("weekly"));
+        draft.set("color", FieldValue::Choice(Some(String::from("red"))));
+        let fields = vec![
+            FormField::switch("news", "News"),
+            FormField::dropdown("color", "Color", vec![String::from("red")]),
+        ];
+        let form = Form::create(Form {
+            draft: Some(draft),
+            ..props(fields, &submitted)
+        });
+
+        assert_eq!(form.values().bool("news"), Some(false));
+        assert!(!form.is_dirty("news"));
+        assert_eq!(form.values().choice("color"), Some("red"));
+    }
+}
@@ -0,0 +1,148 @@
+use vgtk::lib::gtk::prelude::*;
+use vgtk::lib::gtk::*;
+use vgtk::{gtk, gtk_if, Component, VNode};
+
+/// Layout shared by the input components: a label next to or above the input, followed by an
+/// optional help line and an error line.
+#[derive(Clone, Debug)]
+pub struct FieldLayout {
+    pub label: Option<String>,
+    pub orientation: Orientation,
+    pub spacing: i32,
+    pub full_width: bool,
+    pub label_widget: String,
+    // Dimmed line under the input
+    pub help_text: Option<String>,
+    pub help_widget: String,
+    pub required: bool,
+    pub required_marker: String,
+    pub required_widget: String,
+    pub error: Option<String>,
+    pub error_widget: String,
+    pub tooltip: Option<String>,
+    pub sensitive: bool,
+    // An underscore in the label marks the Alt shortcut that focuses the input, off by default so
+    // labels can contain underscores
+    pub mnemonic: bool,
+}
+
+impl Default for FieldLayout {
+    fn default() -> Self {
+        Self {
+            label: None,
+            orientation: Orientation::Horizontal,
+            spacing: 10,
+            full_width: false,
+            label_widget: String::new(),
+            help_text: None,
+            help_widget: String::from("help"),
+            required: false,
+            required_marker: String::from("*"),
+            required_widget: String::from("required"),
+            error: None,
+            error_widget: String::from("error"),
+            tooltip: None,
+            sensitive: true,
+            mnemonic: false,
+        }
+    }
+}
+
+impl FieldLayout {
+    /// Wraps the input. `realized` is sent when the label is realized and is expected to be
+    /// ignored by the component.
+    pub fn view<C: Component>(&self, input: VNode<C>, realized: C::Message) -> VNode<C> {
+        let pack_type = if self.full_width { PackType::End } else { PackType::Start };
+        let mnemonic = self.mnemonic;
+        gtk! {
+            <Box orientation=Orientation::Vertical spacing=4 sensitive=self.sensitive
+                property_tooltip_text=self.tooltip.clone()>
+                <Box orientation=self.orientation spacing=self.spacing>
+                    {
+                        gtk_if!(self.label.is_some() => {
+                            <Box orientation=Orientation::Horizontal spacing=2 halign=Align::Start>
+                                <Label label=self.label.clone().unwrap_or_default() use_underline=self.mnemonic
+                                    widget_name=self.label_widget.clone()
+                                    on realize=|label| {
+                                        if mnemonic {
+                                            connect_mnemonic(label);
+                                        }
+                                        realized.clone()
+                                    } />
+                                {
+                                    gtk_if!(self.required => {
+                                        <Label label=self.required_marker.clone() widget_name=self.required_widget.clone() />
+                                    })
+                                }
+                            </Box>
+                        })
+                    }
+                    <Box orientation=Orientation::Horizontal Box::pack_type=pack_type>
+                        { input }
+                    </Box>
+                </Box>
+                { help_view(self.help_text.clone(), self.help_widget.clone()) }
+                {
+                    gtk_if!(self.error.is_some() => {
+                        <Label label=self.error.clone().unwrap_or_default() widget_name=self.error_widget.clone()
+                            halign=Align::Start xalign=0.0 line_wrap=true />
+                    })
+                }
+            </Box>
+        }
+    }
+}
+
+/// The dimmed help line shown under a field, also used by components without a `FieldLayout`.
+pub fn help_view<C: Component>(help_text: Option<String>, widget_name: String) -> VNode<C> {
+    match help_text {
+        // Dimmed like the theme's "dim-label" class
+        Some(help_text) => gtk! {
+            <Label label=help_text widget_name=widget_name halign=Align::Start xalign=0.0 line_wrap=true opacity=0.55 />
+        },
+        None => gtk! {
+            <Box visible=false no_show_all=true />
+        },
+    }
+}
+
+/// Stands in for an input in read-only mode: the value as text that can be selected and copied.
+pub fn read_only_view<C: Component>(text: String, widget_name: String) -> VNode<C> {
+    gtk! {
+        <Label label=text widget_name=widget_name selectable=true halign=Align::Start xalign=0.0 />
+    }
+}
+
+/// An input component laid out with `FieldLayout`.
+pub trait LabeledField: Component {
+    fn layout(&self) -> FieldLayout;
+
+    /// Message for the layout's realize handler, which the component ignores. Usually a
+    /// `#[doc(hidden)]` variant.
+    fn realized() -> Self::Message;
+
+    fn labeled(&self, input: VNode<Self>) -> VNode<Self> {
+        self.layout().view(input, Self::realized())
+    }
+}
+
+// The label sits in its own box next to the input's box in the row
+fn connect_mnemonic(label: &Label) {
+    let row = label
+        .get_parent()
+        .and_then(|parent| parent.get_parent())
+        .and_then(|row| row.downcast::<Container>().ok());
+    let target = row.and_then(|row| row.get_children().iter().skip(1).find_map(focus_target));
+    label.set_mnemonic_widget(target.as_ref());
+}
+
+fn focus_target(widget: &Widget) -> Option<Widget> {
+    if widget.get_can_focus() || widget.is::<ComboBox>() {
+        return Some(widget.clone());
+    }
+    widget
+        .downcast_ref::<Container>()?
+        .get_children()
+        .iter()
+        .find_map(focus_target)
+}
@@ -1,9 +1,24 @@
 #![recursion_limit = "1024"]
 pub mod checkbox;
+pub mod checkbox_group;
 pub mod date;
+#[cfg(feature = "serde")]
+pub mod drafts;
 pub mod dropdown;
 pub mod file_chooser;
+pub mod file_dialog;
+pub mod form;
+pub mod labeled_field;
 pub mod passwordbox;
+pub mod paths;
 pub mod phone_number;
 pub mod progress_bar;
+pub mod progress_task;
+pub mod radio_group;
+pub mod recent_files;
+pub mod schema_form;
+pub mod staged_progress;
+pub mod struct_form;
+pub mod suggestions;
+pub mod switch;
 pub mod textbox;
@@ -1,6 +1,56 @@
 use vgtk::lib::gtk::prelude::*;
 use vgtk::lib::gtk::*;
-use vgtk::{gtk, gtk_if, Callback, Component, UpdateAction, VNode};
+use vgtk::{gtk, Callback, Component, UpdateAction, VNode};
+
+use crate::labeled_field::{read_only_view, FieldLayout, LabeledField};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde_crate::Serialize, serde_crate::Deserialize), serde(crate = "serde_crate"))]
+pub enum PasswordStrength {
+    Weak,
+    Fair,
+    Strong,
+}
+
+/// What a password is made of, without the password itself so it can be stored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde_crate::Serialize, serde_crate::Deserialize), serde(crate = "serde_crate"))]
+pub struct PasswordAssessment {
+    pub length: usize,
+    pub has_lowercase: bool,
+    pub has_uppercase: bool,
+    pub has_digit: bool,
+    pub has_symbol: bool,
+    pub strength: PasswordStrength,
+}
+
+impl PasswordAssessment {
+    pub fn assess(password: &str) -> Self {
+        let length = password.chars().count();
+        let has_lowercase = password.chars().any(char::is_lowercase);
+        let has_uppercase = password.chars().any(char::is_uppercase);
+        let has_digit = password.chars().any(|c| c.is_ascii_digit());
+        let has_symbol = password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace());
+
+        let kinds = [has_lowercase, has_uppercase, has_digit, has_symbol].iter().filter(|has| **has).count();
+        let strength = if length >= 12 && kinds >= 3 {
+            PasswordStrength::Strong
+        } else if length >= 8 && kinds >= 2 {
+            PasswordStrength::Fair
+        } else {
+            PasswordStrength::Weak
+        };
+
+        Self {
+            length,
+            has_lowercase,
+            has_uppercase,
+            has_digit,
+            has_symbol,
+            strength,
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct PasswordBox {
@@ -11,7 +61,23 @@ pub struct PasswordBox {
     pub full_width: bool,
     pub widget_name: String,
     pub label_widget: String,
+    // An underscore in the label marks the Alt shortcut that focuses the input
+    pub mnemonic: bool,
+    pub help_text: Option<String>,
+    // Shown in the empty entry
+    pub placeholder: Option<String>,
+    pub tooltip: Option<String>,
+    pub required: bool,
+    // Shown under the input, e.g. from a form's validation
+    pub error: Option<String>,
+    pub sensitive: bool,
+    // Shows the masked text as a label instead of the entry
+    pub read_only: bool,
     pub on_changed: Callback<String>,
+    pub on_assessed: Callback<PasswordAssessment>,
+    // Text as typed so far, `text` stays what the parent gave
+    draft: String,
+    prop_text: String,
 }
 
 impl Default for PasswordBox {
@@ -24,7 +90,18 @@ impl Default for PasswordBox {
             full_width: false,
             widget_name: String::new(),
             label_widget: String::new(),
+            mnemonic: false,
+            help_text: None,
+            placeholder: None,
+            tooltip: None,
+            required: false,
+            error: None,
+            sensitive: true,
+            read_only: false,
             on_changed: Callback::default(),
+            on_assessed: Callback::default(),
+            draft: String::new(),
+            prop_text: String::new(),
         }
     }
 }
@@ -32,6 +109,9 @@ impl Default for PasswordBox {
 #[derive(Clone, Debug)]
 pub enum PasswordBoxMessage {
     SetValue { value: String },
+    // Sent by the layout's realize handlers, see `LabeledField::realized`
+    #[doc(hidden)]
+    Realized,
 }
 
 impl Component for PasswordBox {
@@ -39,54 +119,75 @@ impl Component for PasswordBox {
     type Properties = Self;
 
     fn create(props: Self::Properties) -> Self {
-        props
+        let mut component = props;
+        component.draft = component.text.clone();
+        component.prop_text = component.text.clone();
+        component
     }
 
     fn change(&mut self, props: Self::Properties) -> UpdateAction<Self> {
+        let draft = std::mem::take(&mut self.draft);
+        let prop_text = std::mem::take(&mut self.prop_text);
+
         *self = props;
+
+        // Keep the typed text unless the parent passes a different one
+        self.draft = if self.text == prop_text { draft } else { self.text.clone() };
+        self.prop_text = self.text.clone();
+
         UpdateAction::Render
     }
 
     fn update(&mut self, msg: Self::Message) -> UpdateAction<Self> {
         match msg {
+            // Rendering the draft fires `changed` as well
+            PasswordBoxMessage::SetValue { value } if value == self.draft => UpdateAction::None,
             PasswordBoxMessage::SetValue { value } => {
+                self.draft = value.clone();
+                self.on_assessed.send(PasswordAssessment::assess(&value));
                 self.on_changed.send(value);
                 UpdateAction::None
             }
+            PasswordBoxMessage::Realized => UpdateAction::None,
         }
     }
 
     fn view(&self) -> VNode<Self> {
-        gtk! {
-            <Box orientation=self.orientation spacing=self.spacing>
-                {
-                    gtk_if!(self.label.is_some() => {
-                        <Label label=self.label.as_ref().unwrap().to_string() widget_name=self.label_widget.clone()
-                            halign=Align::Start />
-                    })
-                }
-                {
-                    if self.full_width {
-                        gtk! {
-                            <Entry text=self.text.clone() input_purpose=InputPurpose::Password visibility=false
-                             widget_name=self.widget_name.clone() Box::pack_type=PackType::End on changed=|entry| {
-                                PasswordBoxMessage::SetValue {
-                                    value: entry.get_text().to_string(),
-                                }
-                            } />
-                        }
-                    } else {
-                        gtk! {
-                            <Entry text=self.text.clone() input_purpose=InputPurpose::Password visibility=false
-                             widget_name=self.widget_name.clone() on changed=|entry| {
-                                PasswordBoxMessage::SetValue {
-                                    value: entry.get_text().to_string(),
-                                }
-                            } />
-                        }
-                    }
+        if self.read_only {
+            // Masked like the entry, only the length can be seen
+            let masked = "•".repeat(self.draft.chars().count());
+            return self.labeled(read_only_view(masked, self.widget_name.clone()));
+        }
+        self.labeled(gtk! {
+            <Entry text=self.draft.clone() input_purpose=InputPurpose::Password visibility=false
+             widget_name=self.widget_name.clone() property_placeholder_text=self.placeholder.clone() on changed=|entry| {
+                PasswordBoxMessage::SetValue {
+                    value: entry.get_text().to_string(),
                 }
-            </Box>
+            } />
+        })
+    }
+}
+
+impl LabeledField for PasswordBox {
+    fn layout(&self) -> FieldLayout {
+        FieldLayout {
+            label: self.label.clone(),
+            orientation: self.orientation,
+            spacing: self.spacing,
+            full_width: self.full_width,
+            label_widget: self.label_widget.clone(),
+            mnemonic: self.mnemonic,
+            help_text: self.help_text.clone(),
+            required: self.required,
+            error: self.error.clone(),
+            tooltip: self.tooltip.clone(),
+            sensitive: self.sensitive,
+            ..FieldLayout::default()
         }
     }
+
+    fn realized() -> Self::Message {
+        PasswordBoxMessage::Realized
+    }
 }
@@ -0,0 +1,306 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{self, Path, PathBuf};
+
+use vgtk::lib::gio::{self, prelude::*};
+use vgtk::lib::glib;
+
+/// What kind of filesystem entry a typed path has to point to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathKind {
+    File,
+    Dir,
+    // Anything that isn't a directory, e.g. a file about to be saved
+    NotDir,
+}
+
+/// Checks applied to paths typed by the user.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PathRequirements {
+    pub must_exist: bool,
+    pub kind: Option<PathKind>,
+    pub readable: bool,
+    // For paths that don't exist yet the parent folder has to be writable
+    pub writable: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathError {
+    Missing,
+    NotAFile,
+    NotAFolder,
+    IsAFolder,
+    NotReadable,
+    NotWritable,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::Missing => write!(f, "The path doesn't exist"),
+            PathError::NotAFile => write!(f, "The path isn't a file"),
+            PathError::NotAFolder => write!(f, "The path isn't a folder"),
+            PathError::IsAFolder => write!(f, "The path is a folder"),
+            PathError::NotReadable => write!(f, "The path can't be read"),
+            PathError::NotWritable => write!(f, "The path can't be written to"),
+        }
+    }
+}
+
+impl PathRequirements {
+    pub fn check(&self, path: &Path) -> Result<(), PathError> {
+        let exists = path.exists();
+        if !exists {
+            if self.must_exist {
+                return Err(PathError::Missing);
+            }
+            if self.writable {
+                let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty());
+                if !parent.map_or(true, is_writable) {
+                    return Err(PathError::NotWritable);
+                }
+            }
+            return Ok(());
+        }
+
+        match self.kind {
+            Some(PathKind::File) if !path.is_file() => return Err(PathError::NotAFile),
+            Some(PathKind::Dir) if !path.is_dir() => return Err(PathError::NotAFolder),
+            Some(PathKind::NotDir) if path.is_dir() => return Err(PathError::IsAFolder),
+            _ => {}
+        }
+
+        if self.readable && !is_readable(path) {
+            return Err(PathError::NotReadable);
+        }
+        if self.writable && !is_writable(path) {
+            return Err(PathError::NotWritable);
+        }
+        Ok(())
+    }
+}
+
+fn is_readable(path: &Path) -> bool {
+    if path.is_dir() {
+        fs::read_dir(path).is_ok()
+    } else {
+        fs::File::open(path).is_ok()
+    }
+}
+
+// Asks the system whether the current user may write to the path, which also takes ownership,
+// groups and ACLs into account unlike the permission bits
+fn is_writable(path: &Path) -> bool {
+    gio::File::new_for_path(path)
+        .query_info("access::can-write", gio::FileQueryInfoFlags::NONE, None::<&gio::Cancellable>)
+        .map_or(false, |info| info.get_attribute_boolean("access::can-write"))
+}
+
+/// Expands a leading `~` to the home folder and `$VAR` / `${VAR}` to environment variables.
+/// Unknown variables are left as they are.
+pub fn expand_path(text: &str) -> PathBuf {
+    let text = text.trim();
+    let mut expanded = String::new();
+
+    let home_relative = text.starts_with('~') && text[1..].chars().next().map_or(true, path::is_separator);
+    let rest = if home_relative {
+        match glib::get_home_dir() {
+            Some(home) => {
+                expanded.push_str(&home.to_string_lossy());
+                &text[1..]
+            }
+            None => text,
+        }
+    } else {
+        text
+    };
+
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            expanded.push(c);
+            continue;
+        }
+
+        let braced = chars.peek() == Some(&'{');
+        if braced {
+            chars.next();
+        }
+        let mut name = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                name.push(c);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        let closed = braced && chars.peek() == Some(&'}');
+        if closed {
+            chars.next();
+        }
+
+        match env::var(&name) {
+            Ok(value) if !name.is_empty() && (closed || !braced) => expanded.push_str(&value),
+            _ => {
+                // Put back what was read
+                expanded.push('$');
+                if braced {
+                    expanded.push('{');
+                }
+                expanded.push_str(&name);
+                if closed {
+                    expanded.push('}');
+                }
+            }
+        }
+    }
+
+    PathBuf::from(expanded)
+}
+
+/// An entry of a folder listed by `list_dir`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FolderEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// Lists a folder for `completions_from`. This blocks on the filesystem, so keep it off the main
+/// thread for folders that could be slow (network mounts, huge folders).
+pub fn list_dir(dir: &Path) -> Vec<FolderEntry> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            Some(FolderEntry {
+                name: entry.file_name().into_string().ok()?,
+                is_dir: entry.path().is_dir(),
+            })
+        })
+        .collect()
+}
+
+/// Splits a partly typed path into the folder as typed and the start of the name, e.g.
+/// "~/Doc" into "~/" and "Doc". Returns None until a separator is typed.
+pub fn split_typed_path(text: &str) -> Option<(&str, &str)> {
+    let split = text.rfind(path::is_separator)? + 1;
+    Some(text.split_at(split))
+}
+
+/// Completions for a partly typed path: the entries of the typed folder starting with the typed
+/// name, written the way the user typed the folder. Folders end with a separator.
+pub fn path_completions(text: &str, limit: usize) -> Vec<String> {
+    match split_typed_path(text) {
+        Some((typed_dir, prefix)) => completions_from(&list_dir(&expand_path(typed_dir)), typed_dir, prefix, limit),
+        None => vec![],
+    }
+}
+
+/// `path_completions` for a folder listed beforehand.
+pub fn completions_from(listing: &[FolderEntry], typed_dir: &str, prefix: &str, limit: usize) -> Vec<String> {
+    let mut completions: Vec<String> = listing
+        .iter()
+        // Hidden entries only show up once a dot is typed
+        .filter(|entry| entry.name.starts_with(prefix) && (!entry.name.starts_with('.') || prefix.starts_with('.')))
+        .map(|entry| {
+            let separator = if entry.is_dir { path::MAIN_SEPARATOR.to_string() } else { String::new() };
+            format!("{}{}{}", typed_dir, entry.name, separator)
+        })
+        .collect();
+    completions.sort();
+    completions.truncate(limit);
+    completions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, is_dir: bool) -> FolderEntry {
+        FolderEntry {
+            name: name.to_string(),
+            is_dir,
+        }
+    }
+
+    #[test]
+    fn expand_path_replaces_variables() {
+        env::set_var("VGTK_COMPONENTS_TEST_DIR", "/data");
+        assert_eq!(expand_path("$VGTK_COMPONENTS_TEST_DIR/a.txt"), PathBuf::from("/data/a.txt"));
+        assert_eq!(expand_path("${VGTK_COMPONENTS_TEST_DIR}x"), PathBuf::from("/datax"));
+        assert_eq!(expand_path("  /tmp/a.txt "), PathBuf::from("/tmp/a.txt"));
+        // Unknown and unclosed variables are kept
+        assert_eq!(expand_path("/$VGTK_COMPONENTS_UNSET/a"), PathBuf::from("/$VGTK_COMPONENTS_UNSET/a"));
+        assert_eq!(expand_path("/${VGTK_COMPONENTS_TEST_DIR"), PathBuf::from("/${VGTK_COMPONENTS_TEST_DIR"));
+        assert_eq!(expand_path("/a$"), PathBuf::from("/a$"));
+    }
+
+    #[test]
+    fn expand_path_replaces_a_leading_tilde() {
+        let home = glib::get_home_dir().unwrap();
+        assert_eq!(expand_path("~"), home);
+        assert_eq!(expand_path("~/notes"), home.join("notes"));
+        // Only on its own, not for another user's home or inside a name
+        assert_eq!(expand_path("~bob/notes"), PathBuf::from("~bob/notes"));
+        assert_eq!(expand_path("/tmp/~"), PathBuf::from("/tmp/~"));
+    }
+
+    #[test]
+    fn split_typed_path_splits_at_the_last_separator() {
+        assert_eq!(split_typed_path("~/Doc"), Some(("~/", "Doc")));
+        assert_eq!(split_typed_path("/home/ann/"), Some(("/home/ann/", "")));
+        assert_eq!(split_typed_path("/"), Some(("/", "")));
+        assert_eq!(split_typed_path("Doc"), None);
+    }
+
+    #[test]
+    fn completions_from_filters_and_sorts_the_listing() {
+        let listing = vec![
+            entry("notes.txt", false),
+            entry("Documents", true),
+            entry("nested", true),
+            entry(".notes", false),
+        ];
+        let separator = path::MAIN_SEPARATOR;
+
+        assert_eq!(
+            completions_from(&listing, "~/", "n", 10),
+            vec![format!("~/nested{}", separator), String::from("~/notes.txt")]
+        );
+        assert_eq!(completions_from(&listing, "~/", "n", 1), vec![format!("~/nested{}", separator)]);
+        // Case matters, hidden entries need a dot
+        assert!(completions_from(&listing, "~/", "doc", 10).is_empty());
+        assert_eq!(completions_from(&listing, "~/", ".", 10), vec![String::from("~/.notes")]);
+        assert_eq!(completions_from(&listing, "~/", "", 10).len(), 3);
+    }
+
+    #[test]
+    fn requirements_check_existing_and_new_paths() {
+        let dir = env::temp_dir().join("vgtk_components_paths_test");
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("a.txt");
+        fs::write(&file, "a").unwrap();
+
+        let requirements = PathRequirements {
+            kind: Some(PathKind::File),
+            readable: true,
+            writable: true,
+            ..PathRequirements::default()
+        };
+        assert_eq!(requirements.check(&file), Ok(()));
+        assert_eq!(requirements.check(&dir), Err(PathError::NotAFile));
+        assert_eq!(requirements.check(&dir.join("new.txt")), Ok(()));
+        let existing = PathRequirements {
+            must_exist: true,
+            ..PathRequirements::default()
+        };
+        assert_eq!(existing.check(&dir.join("new.txt")), Err(PathError::Missing));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
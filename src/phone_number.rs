@@ -1,6 +1,35 @@
+use std::fmt;
+
 use vgtk::lib::gtk::prelude::*;
 use vgtk::lib::gtk::*;
-use vgtk::{gtk, gtk_if, Callback, Component, UpdateAction, VNode};
+use vgtk::{gtk, Callback, Component, UpdateAction, VNode};
+
+use crate::labeled_field::{read_only_view, FieldLayout, LabeledField};
+
+/// A phone number as typed into `PhoneNumber`, without formatting.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde_crate::Serialize, serde_crate::Deserialize), serde(crate = "serde_crate"))]
+pub struct Phone {
+    pub digits: String,
+}
+
+impl Phone {
+    pub fn new(text: &str) -> Self {
+        Self {
+            digits: text.chars().filter(char::is_ascii_digit).collect(),
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.digits.len() == 10
+    }
+}
+
+impl fmt::Display for Phone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", pretty_format_phone_number(&self.digits))
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct PhoneNumber {
@@ -11,7 +40,24 @@ pub struct PhoneNumber {
     pub full_width: bool,
     pub widget_name: String,
     pub label_widget: String,
+    // An underscore in the label marks the Alt shortcut that focuses the input
+    pub mnemonic: bool,
+    pub help_text: Option<String>,
+    // Shown in the empty entry
+    pub placeholder: Option<String>,
+    pub tooltip: Option<String>,
+    pub required: bool,
+    // Shown under the input, e.g. from a form's validation
+    pub error: Option<String>,
+    pub sensitive: bool,
+    // Shows the formatted number as a label instead of the entry
+    pub read_only: bool,
     pub on_changed: Callback<String>,
+    // Same as `on_changed`, as a typed value
+    pub on_phone: Callback<Phone>,
+    // Digits typed so far, `text` stays what the parent gave
+    draft: String,
+    prop_text: String,
 }
 
 impl Default for PhoneNumber {
@@ -24,7 +70,18 @@ impl Default for PhoneNumber {
             full_width: false,
             widget_name: String::new(),
             label_widget: String::new(),
+            mnemonic: false,
+            help_text: None,
+            placeholder: None,
+            tooltip: None,
+            required: false,
+            error: None,
+            sensitive: true,
+            read_only: false,
             on_changed: Callback::default(),
+            on_phone: Callback::default(),
+            draft: String::new(),
+            prop_text: String::new(),
         }
     }
 }
@@ -32,6 +89,12 @@ impl Default for PhoneNumber {
 #[derive(Clone, Debug)]
 pub enum PhoneNumberMessage {
     SetValue { value: String },
+    // Sent by `insert_text` once the text is formatted, the new value follows with `changed`
+    #[doc(hidden)]
+    Formatted,
+    // Sent by the layout's realize handlers, see `LabeledField::realized`
+    #[doc(hidden)]
+    Realized,
 }
 
 impl Component for PhoneNumber {
@@ -39,56 +102,97 @@ impl Component for PhoneNumber {
     type Properties = Self;
 
     fn create(props: Self::Properties) -> Self {
-        props
+        let mut component = props;
+        component.draft = Phone::new(&component.text).digits;
+        component.prop_text = component.text.clone();
+        component
     }
 
     fn change(&mut self, props: Self::Properties) -> UpdateAction<Self> {
+        let draft = std::mem::take(&mut self.draft);
+        let prop_text = std::mem::take(&mut self.prop_text);
+
         *self = props;
+
+        // Keep the typed digits unless the parent passes a different number
+        self.draft = if self.text == prop_text { draft } else { Phone::new(&self.text).digits };
+        self.prop_text = self.text.clone();
+
         UpdateAction::Render
     }
 
     fn update(&mut self, msg: Self::Message) -> UpdateAction<Self> {
         match msg {
+            // Rendering the draft fires `changed` as well
+            PhoneNumberMessage::SetValue { value } if value == self.draft => UpdateAction::None,
             PhoneNumberMessage::SetValue { value } => {
+                self.draft = value.clone();
+                self.on_phone.send(Phone::new(&value));
                 self.on_changed.send(value);
-                UpdateAction::Render
+                UpdateAction::None
             }
+            PhoneNumberMessage::Formatted | PhoneNumberMessage::Realized => UpdateAction::None,
         }
     }
 
     fn view(&self) -> VNode<Self> {
-        gtk! {
-            <Box orientation=self.orientation spacing=self.spacing>
-                {
-                    gtk_if!(self.label.is_some() => {
-                        <Label label=self.label.as_ref().unwrap().to_string() widget_name=self.label_widget.clone()
-                            halign=Align::Start />
-                    })
-                }
-                {
-                    let pack_type = if self.full_width { PackType::End } else { PackType::Start };
-                    gtk! {
-                        <Entry widget_name=self.widget_name.clone() Box::pack_type=pack_type max_length=14 on insert_text=|entry, text, position| {
-                            let mut raw_phone_number = entry.get_text().to_string();
-                            raw_phone_number.push_str(text);
-
-                            let phone_number = unpretty_format_phone_number(&raw_phone_number);
-                            let pretty_phone_number = pretty_format_phone_number(&phone_number);
-                            if raw_phone_number != pretty_phone_number {
-                                // ignore the last char as that would be added by user input
-                                entry.set_text(&pretty_format_phone_number(&phone_number[..phone_number.len()-1]));
-                                *position = pretty_phone_number.len() as i32;
-                            }
-                            PhoneNumberMessage::SetValue {
-                                value: phone_number,
-                            }
-                        } />
-                    }
+        if self.read_only {
+            return self.labeled(read_only_view(self.formatted(), self.widget_name.clone()));
+        }
+        self.labeled(gtk! {
+            <Entry text=self.formatted() widget_name=self.widget_name.clone() max_length=14
+             property_placeholder_text=self.placeholder.clone() on insert_text=|entry, text, position| {
+                let mut raw_phone_number = entry.get_text().to_string();
+                raw_phone_number.push_str(text);
+
+                let phone_number = unpretty_format_phone_number(&raw_phone_number);
+                let pretty_phone_number = pretty_format_phone_number(&phone_number);
+                if raw_phone_number != pretty_phone_number {
+                    // ignore the last char as that would be added by user input
+                    entry.set_text(&pretty_format_phone_number(&phone_number[..phone_number.len()-1]));
+                    *position = pretty_phone_number.len() as i32;
                 }
-            </Box>
+                PhoneNumberMessage::Formatted
+            }
+            on changed=|entry| PhoneNumberMessage::SetValue {
+                value: unpretty_format_phone_number(&entry.get_text()),
+            } />
+        })
+    }
+}
+
+impl PhoneNumber {
+    // The typed digits as shown in the entry, empty rather than a lone "("
+    fn formatted(&self) -> String {
+        if self.draft.is_empty() {
+            String::new()
+        } else {
+            pretty_format_phone_number(&self.draft)
         }
     }
+}
 
+impl LabeledField for PhoneNumber {
+    fn layout(&self) -> FieldLayout {
+        FieldLayout {
+            label: self.label.clone(),
+            orientation: self.orientation,
+            spacing: self.spacing,
+            full_width: self.full_width,
+            label_widget: self.label_widget.clone(),
+            mnemonic: self.mnemonic,
+            help_text: self.help_text.clone(),
+            required: self.required,
+            error: self.error.clone(),
+            tooltip: self.tooltip.clone(),
+            sensitive: self.sensitive,
+            ..FieldLayout::default()
+        }
+    }
+
+    fn realized() -> Self::Message {
+        PhoneNumberMessage::Realized
+    }
 }
 
 fn pretty_format_phone_number(phone_number: &str) -> String {
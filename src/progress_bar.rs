@@ -1,39 +1,477 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+use vgtk::lib::glib::{self, Continue, WeakRef};
 use vgtk::lib::gtk::prelude::*;
 use vgtk::lib::gtk::ProgressBar as GtkProgressBar;
-use vgtk::{gtk, Component, UpdateAction, VNode};
+use vgtk::lib::gtk::*;
+use vgtk::{gtk, gtk_if, Callback, Component, UpdateAction, VNode};
 
-#[derive(Clone, Debug, Default)]
+use crate::progress_task::{self, ProgressTask, ProgressUpdate};
+
+#[derive(Clone, Debug)]
 pub struct ProgressBar {
     pub progress_fraction: f64,
     pub progress_text: String,
     pub show_text: bool,
+    // Builds the shown text from placeholders instead of using `progress_text` as is, see
+    // `format_template` for the names
+    pub text_format: Option<String>,
     pub widget_name: String,
+    // Bounces a block back and forth instead of showing `progress_fraction`, for tasks of unknown
+    // length. Turning it off goes back to `progress_fraction` on the same widget.
+    pub pulsing: bool,
+    // Fraction of the bar the block moves per pulse
+    pub pulse_step: f64,
+    pub pulse_interval_ms: u32,
+    // Work reported from a `ProgressHandle`. Replaces `progress_fraction` once the worker reports a
+    // total and pulses until then.
+    pub task: Option<ProgressTask>,
+    // Elapsed time, throughput and ETA below the bar, only with a task
+    pub show_stats: bool,
+    // What the task counts, e.g. "files" in "12.5 files/s"
+    pub rate_unit: String,
+    // Offers a button that cancels the task
+    pub cancellable: bool,
+    pub cancel_label: String,
+    pub stats_widget: String,
+    pub tooltip: Option<String>,
+    pub sensitive: bool,
+    // Follows the task without offering to cancel it
+    pub read_only: bool,
+    pub on_cancel: Callback<()>,
+    pub on_finished: Callback<()>,
+    // Shared with the pulse timer, kept across `change`
+    pulse: Rc<PulseState>,
+    done: u64,
+    total: Option<u64>,
+    task_text: Option<String>,
+    elapsed: Duration,
+    finished: bool,
+}
+
+impl Default for ProgressBar {
+    fn default() -> Self {
+        Self {
+            progress_fraction: 0.0,
+            progress_text: String::new(),
+            show_text: false,
+            text_format: None,
+            widget_name: String::new(),
+            pulsing: false,
+            pulse_step: 0.1,
+            pulse_interval_ms: 100,
+            task: None,
+            show_stats: true,
+            rate_unit: String::from("items"),
+            cancellable: false,
+            cancel_label: String::from("Cancel"),
+            stats_widget: String::from("stats"),
+            tooltip: None,
+            sensitive: true,
+            read_only: false,
+            on_cancel: Callback::default(),
+            on_finished: Callback::default(),
+            pulse: Rc::default(),
+            done: 0,
+            total: None,
+            task_text: None,
+            elapsed: Duration::default(),
+            finished: false,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PulseState {
+    bar: RefCell<Option<WeakRef<GtkProgressBar>>>,
+    pulsing: Cell<bool>,
+    interval_ms: Cell<u32>,
+    fraction: Cell<f64>,
+    // Whether a timer is currently scheduled
+    running: Cell<bool>,
+}
+
+impl PulseState {
+    fn bar(&self) -> Option<GtkProgressBar> {
+        self.bar.borrow().as_ref().and_then(|bar| bar.upgrade())
+    }
 }
 
 #[derive(Clone, Debug)]
-pub enum ProgressBarMessage {}
+pub enum ProgressBarMessage {
+    // Sent when the bar is realized to start the pulse timer
+    #[doc(hidden)]
+    Realized,
+    Updates { updates: Vec<ProgressUpdate> },
+    Cancel,
+}
+
+impl ProgressBar {
+    fn sync_pulse(&self) {
+        let pulsing = self.is_pulsing();
+        self.pulse.pulsing.set(pulsing);
+        self.pulse.interval_ms.set(self.pulse_interval_ms.max(1));
+        self.pulse.fraction.set(self.fraction());
+        if pulsing && !self.pulse.running.get() {
+            schedule_pulse(&self.pulse);
+        }
+    }
+
+    fn is_pulsing(&self) -> bool {
+        match self.task {
+            Some(_) if !self.finished => self.pulsing || self.total.is_none(),
+            _ => self.pulsing,
+        }
+    }
+
+    fn fraction(&self) -> f64 {
+        match (&self.task, self.total) {
+            (Some(_), Some(total)) if total > 0 => (self.done as f64 / total as f64).min(1.0),
+            (Some(_), _) if self.finished => 1.0,
+            _ => self.progress_fraction,
+        }
+    }
+
+    fn text(&self) -> String {
+        let text = match &self.task_text {
+            Some(text) if self.task.is_some() => text.clone(),
+            _ => self.progress_text.clone(),
+        };
+        let format = match &self.text_format {
+            Some(format) => format,
+            None => return text,
+        };
+
+        let eta = self
+            .total
+            .and_then(|total| progress_task::eta(self.done, total, self.elapsed))
+            .map(progress_task::format_duration);
+        let rate = progress_task::rate(self.done, self.elapsed).map(|rate| format!("{:.1}", rate));
+        format_template(
+            format,
+            &[
+                ("percent", format!("{:.0}", self.fraction() * 100.0)),
+                ("done", self.done.to_string()),
+                ("total", self.total.map(|total| total.to_string()).unwrap_or_default()),
+                ("elapsed", progress_task::format_duration(self.elapsed)),
+                ("eta", eta.unwrap_or_default()),
+                ("rate", rate.unwrap_or_default()),
+                ("text", text),
+            ],
+        )
+    }
+
+    fn stats(&self) -> String {
+        let mut stats = vec![format!("{} elapsed", progress_task::format_duration(self.elapsed))];
+        if let Some(rate) = progress_task::rate(self.done, self.elapsed) {
+            stats.push(format!("{:.1} {}/s", rate, self.rate_unit));
+        }
+        if let Some(total) = self.total.filter(|_| !self.finished) {
+            if let Some(eta) = progress_task::eta(self.done, total, self.elapsed) {
+                stats.push(format!("{} left", progress_task::format_duration(eta)));
+            }
+        }
+        stats.join(" · ")
+    }
+
+    // Setting the fraction leaves activity mode, so a pulsing bar is rendered without it and the
+    // pulse timer puts the fraction back once pulsing stops
+    fn bar_view(&self, in_row: bool) -> VNode<Self> {
+        // In a row the surrounding box gets these instead
+        let sensitive = self.sensitive || in_row;
+        let tooltip = self.tooltip.clone().filter(|_| !in_row);
+        let pulse = self.pulse.clone();
+        let realized = move |bar: &GtkProgressBar| {
+            pulse.bar.replace(Some(bar.downgrade()));
+            ProgressBarMessage::Realized
+        };
+        match (self.is_pulsing(), in_row) {
+            (true, true) => gtk! {
+                <GtkProgressBar text=self.text() valign=Align::Center Box::expand=true
+                    show_text=self.show_text pulse_step=self.pulse_step widget_name=self.widget_name.clone()
+                    sensitive=sensitive property_tooltip_text=tooltip.clone() on realize=|bar| realized(bar) />
+            },
+            (false, true) => gtk! {
+                <GtkProgressBar fraction=self.fraction() text=self.text() valign=Align::Center Box::expand=true
+                    show_text=self.show_text pulse_step=self.pulse_step widget_name=self.widget_name.clone()
+                    sensitive=sensitive property_tooltip_text=tooltip.clone() on realize=|bar| realized(bar) />
+            },
+            (true, false) => gtk! {
+                <GtkProgressBar text=self.text() valign=Align::Center
+                    show_text=self.show_text pulse_step=self.pulse_step widget_name=self.widget_name.clone()
+                    sensitive=sensitive property_tooltip_text=tooltip.clone() on realize=|bar| realized(bar) />
+            },
+            (false, false) => gtk! {
+                <GtkProgressBar fraction=self.fraction() text=self.text() valign=Align::Center
+                    show_text=self.show_text pulse_step=self.pulse_step widget_name=self.widget_name.clone()
+                    sensitive=sensitive property_tooltip_text=tooltip.clone() on realize=|bar| realized(bar) />
+            },
+        }
+    }
+}
+
+/// Replaces `{name}` placeholders with their values. `ProgressBar` provides `percent`, `done`,
+/// `total`, `elapsed`, `eta`, `rate` and `text`; unknown placeholders are left as they are.
+pub fn format_template(template: &str, values: &[(&str, String)]) -> String {
+    let mut formatted = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        formatted.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find('}').and_then(|end| {
+            let name = &rest[1..end];
+            values.iter().find(|(key, _)| *key == name).map(|(_, value)| (value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                formatted.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                formatted.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    formatted.push_str(rest);
+    formatted
+}
+
+/// Pulses the bar every `interval_ms` until pulsing is turned off or the widget is gone. A changed
+/// interval replaces the timer with a new one.
+fn schedule_pulse(state: &Rc<PulseState>) {
+    if state.bar().is_none() {
+        // Not realized yet, `Realized` starts the timer
+        return;
+    }
+
+    state.running.set(true);
+    let interval_ms = state.interval_ms.get();
+    let state = state.clone();
+    glib::timeout_add_local(interval_ms, move || {
+        let bar = match state.bar() {
+            Some(bar) => bar,
+            None => {
+                state.running.set(false);
+                return Continue(false);
+            }
+        };
+        if !state.pulsing.get() {
+            // Setting the fraction leaves activity mode
+            bar.set_fraction(state.fraction.get());
+            state.running.set(false);
+            return Continue(false);
+        }
+        if state.interval_ms.get() != interval_ms {
+            schedule_pulse(&state);
+            return Continue(false);
+        }
+        bar.pulse();
+        Continue(true)
+    });
+}
 
 impl Component for ProgressBar {
     type Message = ProgressBarMessage;
     type Properties = Self;
 
     fn create(props: Self::Properties) -> Self {
+        props.sync_pulse();
         props
     }
 
     fn change(&mut self, props: Self::Properties) -> UpdateAction<Self> {
+        let pulse = self.pulse.clone();
+        let task = self.task.clone();
+        let (done, total, task_text, elapsed, finished) =
+            (self.done, self.total, self.task_text.take(), self.elapsed, self.finished);
+
         *self = props;
+        self.pulse = pulse;
+        // A new task starts from scratch
+        if self.task == task {
+            self.done = done;
+            self.total = total;
+            self.task_text = task_text;
+            self.elapsed = elapsed;
+            self.finished = finished;
+        }
+        self.sync_pulse();
         UpdateAction::Render
     }
 
-    fn update(&mut self, _msg: Self::Message) -> UpdateAction<Self> {
-        UpdateAction::None
+    fn update(&mut self, msg: Self::Message) -> UpdateAction<Self> {
+        match msg {
+            ProgressBarMessage::Realized => {
+                self.sync_pulse();
+                UpdateAction::None
+            }
+            ProgressBarMessage::Updates { updates } => {
+                if let Some(task) = &self.task {
+                    self.elapsed = task.elapsed();
+                }
+                for update in updates {
+                    match update {
+                        ProgressUpdate::Progress { done, total } => {
+                            self.done = done;
+                            self.total = total;
+                        }
+                        ProgressUpdate::Text(text) => self.task_text = Some(text),
+                        ProgressUpdate::Finished => {
+                            self.finished = true;
+                            self.on_finished.send(());
+                        }
+                    }
+                }
+                self.sync_pulse();
+                UpdateAction::Render
+            }
+            ProgressBarMessage::Cancel if self.read_only => UpdateAction::None,
+            ProgressBarMessage::Cancel => {
+                if let Some(task) = &self.task {
+                    task.cancel();
+                    self.on_cancel.send(());
+                }
+                UpdateAction::Render
+            }
+        }
+    }
+
+    fn view(&self) -> VNode<Self> {
+        // The extra boxes and the listener are only needed to follow a task
+        if self.task.is_none() {
+            return self.bar_view(false);
+        }
+        let running = self.task.as_ref().map_or(false, |task| !self.finished && !task.is_cancelled());
+        gtk! {
+            <Box orientation=Orientation::Vertical spacing=4 sensitive=self.sensitive
+                property_tooltip_text=self.tooltip.clone()>
+                <Box orientation=Orientation::Horizontal spacing=10>
+                    { self.bar_view(true) }
+                    {
+                        gtk_if!(self.cancellable && !self.read_only => {
+                            <Button label=self.cancel_label.clone() sensitive=running
+                                on clicked=|_| ProgressBarMessage::Cancel />
+                        })
+                    }
+                </Box>
+                <@TaskListener task=self.task.clone() on_updates=|updates| ProgressBarMessage::Updates { updates } />
+                {
+                    gtk_if!(self.show_stats => {
+                        <Label label=self.stats() halign=Align::Start widget_name=self.stats_widget.clone() />
+                    })
+                }
+            </Box>
+        }
+    }
+}
+
+// Waits for a task's updates and hands them to the `ProgressBar`. It lives in its own component
+// because a deferred update doesn't render, so the bar couldn't show a batch and keep waiting for
+// the next one at the same time.
+#[derive(Clone, Debug, Default)]
+struct TaskListener {
+    task: Option<ProgressTask>,
+    on_updates: Callback<Vec<ProgressUpdate>>,
+    // Bumped for every new task so pending updates of the previous one are dropped
+    generation: usize,
+    // Whether updates of the current task are being waited for, a realize doesn't start a
+    // second listener
+    listening: bool,
+}
+
+#[derive(Clone, Debug)]
+enum TaskListenerMessage {
+    Listen,
+    Updates { updates: Vec<ProgressUpdate>, generation: usize },
+}
+
+impl TaskListener {
+    fn listen(&mut self) -> UpdateAction<Self> {
+        match &self.task {
+            Some(task) => {
+                self.listening = true;
+                let updates = task.next_updates();
+                let generation = self.generation;
+                UpdateAction::defer(async move {
+                    TaskListenerMessage::Updates {
+                        updates: updates.await,
+                        generation,
+                    }
+                })
+            }
+            None => UpdateAction::None,
+        }
+    }
+}
+
+impl Component for TaskListener {
+    type Message = TaskListenerMessage;
+    type Properties = Self;
+
+    fn create(props: Self::Properties) -> Self {
+        props
+    }
+
+    fn change(&mut self, props: Self::Properties) -> UpdateAction<Self> {
+        let task = self.task.clone();
+        let (generation, listening) = (self.generation, self.listening);
+
+        *self = props;
+        self.generation = generation;
+        self.listening = listening;
+        if self.task == task {
+            return UpdateAction::None;
+        }
+        self.generation += 1;
+        self.listening = false;
+        self.listen()
+    }
+
+    fn update(&mut self, msg: Self::Message) -> UpdateAction<Self> {
+        match msg {
+            TaskListenerMessage::Listen if self.listening => UpdateAction::None,
+            TaskListenerMessage::Listen => self.listen(),
+            TaskListenerMessage::Updates { generation, .. } if generation != self.generation => UpdateAction::None,
+            TaskListenerMessage::Updates { updates, .. } => {
+                let finished = updates.contains(&ProgressUpdate::Finished);
+                self.on_updates.send(updates);
+                if finished {
+                    UpdateAction::None
+                } else {
+                    self.listen()
+                }
+            }
+        }
     }
 
     fn view(&self) -> VNode<Self> {
         gtk! {
-            <GtkProgressBar fraction=self.progress_fraction text=self.progress_text.clone()
-                show_text=self.show_text widget_name=self.widget_name.clone() />
+            <Box visible=false no_show_all=true on realize=|_| TaskListenerMessage::Listen />
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_template_replaces_known_placeholders() {
+        let values = [("percent", String::from("42")), ("text", String::from("Copying"))];
+        assert_eq!(format_template("{text}: {percent}%", &values), "Copying: 42%");
+        assert_eq!(format_template("{percent}{percent}", &values), "4242");
+        assert_eq!(format_template("no placeholders", &values), "no placeholders");
+    }
+
+    #[test]
+    fn format_template_keeps_unknown_and_unclosed_braces() {
+        let values = [("percent", String::from("42"))];
+        assert_eq!(format_template("{eta} left", &values), "{eta} left");
+        assert_eq!(format_template("{percent", &values), "{percent");
+        assert_eq!(format_template("{{percent}}", &values), "{42}");
+        assert_eq!(format_template("ünï {percent}", &values), "ünï 42");
+    }
+}
@@ -0,0 +1,231 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::{Rc, Weak};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use vgtk::lib::glib::{self, Continue, MainContext};
+
+/// Sent from the worker to the `ProgressTask`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProgressUpdate {
+    // Units of work done so far, out of `total` when it's known
+    Progress { done: u64, total: Option<u64> },
+    Text(String),
+    Finished,
+}
+
+/// Worker side of a `ProgressTask`. It can be cloned and sent to a thread or an async task.
+/// Updates sent after the task is gone are dropped.
+#[derive(Clone, Debug)]
+pub struct ProgressHandle {
+    sender: glib::Sender<ProgressUpdate>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ProgressHandle {
+    pub fn set_progress(&self, done: u64, total: Option<u64>) {
+        let _ = self.sender.send(ProgressUpdate::Progress { done, total });
+    }
+
+    pub fn set_text(&self, text: &str) {
+        let _ = self.sender.send(ProgressUpdate::Text(text.to_string()));
+    }
+
+    pub fn finish(&self) {
+        let _ = self.sender.send(ProgressUpdate::Finished);
+    }
+
+    /// Whether the user asked to cancel. Workers are expected to poll this and stop early.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// UI side of a task reporting progress, given to `ProgressBar::task`. Has to be created on the
+/// main thread.
+#[derive(Clone)]
+pub struct ProgressTask {
+    inner: Rc<TaskInner>,
+}
+
+struct TaskInner {
+    inbox: RefCell<VecDeque<ProgressUpdate>>,
+    waker: RefCell<Option<Waker>>,
+    // Set once a second so elapsed time and ETA keep moving between updates
+    ticked: Cell<bool>,
+    finished: Cell<bool>,
+    cancelled: Arc<AtomicBool>,
+    started: Instant,
+}
+
+impl TaskInner {
+    fn wake(&self) {
+        if let Some(waker) = self.waker.borrow_mut().take() {
+            waker.wake();
+        }
+    }
+}
+
+impl fmt::Debug for ProgressTask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressTask")
+            .field("started", &self.inner.started)
+            .field("finished", &self.inner.finished.get())
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+impl PartialEq for ProgressTask {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl ProgressTask {
+    pub fn new() -> (ProgressTask, ProgressHandle) {
+        let (sender, receiver) = MainContext::channel(glib::PRIORITY_DEFAULT);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let inner = Rc::new(TaskInner {
+            inbox: RefCell::new(VecDeque::new()),
+            waker: RefCell::new(None),
+            ticked: Cell::new(false),
+            finished: Cell::new(false),
+            cancelled: cancelled.clone(),
+            started: Instant::now(),
+        });
+
+        // Both sources only hold weak references so a dropped task stops them
+        let weak: Weak<TaskInner> = Rc::downgrade(&inner);
+        receiver.attach(None, move |update| match weak.upgrade() {
+            Some(inner) => {
+                let finished = update == ProgressUpdate::Finished;
+                inner.inbox.borrow_mut().push_back(update);
+                inner.wake();
+                Continue(!finished)
+            }
+            None => Continue(false),
+        });
+        let weak: Weak<TaskInner> = Rc::downgrade(&inner);
+        glib::timeout_add_seconds_local(1, move || match weak.upgrade() {
+            Some(inner) if !inner.finished.get() => {
+                inner.ticked.set(true);
+                inner.wake();
+                Continue(true)
+            }
+            _ => Continue(false),
+        });
+
+        let handle = ProgressHandle { sender, cancelled };
+        (ProgressTask { inner }, handle)
+    }
+
+    /// Resolves with the updates received since the last call, or with none once a second has
+    /// passed without any.
+    pub fn next_updates(&self) -> impl Future<Output = Vec<ProgressUpdate>> + 'static {
+        NextUpdates {
+            inner: self.inner.clone(),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.inner.started.elapsed()
+    }
+}
+
+struct NextUpdates {
+    inner: Rc<TaskInner>,
+}
+
+impl Future for NextUpdates {
+    type Output = Vec<ProgressUpdate>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = &self.inner;
+        let ticked = inner.ticked.replace(false);
+        if !ticked && inner.inbox.borrow().is_empty() {
+            *inner.waker.borrow_mut() = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        let updates: Vec<_> = inner.inbox.borrow_mut().drain(..).collect();
+        if updates.contains(&ProgressUpdate::Finished) {
+            inner.finished.set(true);
+        }
+        Poll::Ready(updates)
+    }
+}
+
+/// Work done per second so far.
+pub fn rate(done: u64, elapsed: Duration) -> Option<f64> {
+    let seconds = elapsed.as_secs_f64();
+    if seconds > 0.0 {
+        Some(done as f64 / seconds)
+    } else {
+        None
+    }
+}
+
+/// Time left at the current rate.
+pub fn eta(done: u64, total: u64, elapsed: Duration) -> Option<Duration> {
+    match rate(done, elapsed) {
+        Some(rate) if rate > 0.0 => Some(Duration::from_secs_f64(total.saturating_sub(done) as f64 / rate)),
+        _ => None,
+    }
+}
+
+/// `m:ss`, or `h:mm:ss` from an hour on.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_needs_elapsed_time() {
+        assert_eq!(rate(10, Duration::from_secs(4)), Some(2.5));
+        assert_eq!(rate(0, Duration::from_secs(4)), Some(0.0));
+        assert_eq!(rate(10, Duration::default()), None);
+    }
+
+    #[test]
+    fn eta_extrapolates_the_rate() {
+        assert_eq!(eta(25, 100, Duration::from_secs(10)), Some(Duration::from_secs(30)));
+        assert_eq!(eta(100, 100, Duration::from_secs(10)), Some(Duration::default()));
+        // More done than expected doesn't go negative
+        assert_eq!(eta(120, 100, Duration::from_secs(10)), Some(Duration::default()));
+        assert_eq!(eta(0, 100, Duration::from_secs(10)), None);
+        assert_eq!(eta(10, 100, Duration::default()), None);
+    }
+
+    #[test]
+    fn format_duration_adds_hours_when_needed() {
+        assert_eq!(format_duration(Duration::from_secs(0)), "0:00");
+        assert_eq!(format_duration(Duration::from_millis(65_900)), "1:05");
+        assert_eq!(format_duration(Duration::from_secs(3599)), "59:59");
+        assert_eq!(format_duration(Duration::from_secs(3600)), "1:00:00");
+        assert_eq!(format_duration(Duration::from_secs(90_061)), "25:01:01");
+    }
+}
@@ -0,0 +1,196 @@
+use std::fmt::Debug;
+
+use vgtk::lib::gtk::prelude::*;
+use vgtk::lib::gtk::*;
+use vgtk::{gtk, gtk_if, Callback, Component, UpdateAction, VNode};
+
+use crate::checkbox_group::{GroupOption, Selection};
+use crate::labeled_field::{FieldLayout, LabeledField};
+
+#[derive(Clone, Debug)]
+pub struct RadioGroup<T> {
+    pub label: Option<String>,
+    pub options: Vec<GroupOption<T>>,
+    // Initially selected value, the group keeps track of it afterwards
+    pub selected: Option<T>,
+    pub orientation: Orientation,
+    pub spacing: i32,
+    pub widget_name: String,
+    pub label_widget: String,
+    pub help_text: Option<String>,
+    pub description_widget: String,
+    pub tooltip: Option<String>,
+    pub sensitive: bool,
+    // Shows the choice without letting the user change it
+    pub read_only: bool,
+    pub on_changed: Callback<T>,
+    // Same as `on_changed`, with the option's position
+    pub on_selection: Callback<Selection<T>>,
+    // Last selection given by the parent, used to spot changes
+    prop_selected: Option<T>,
+}
+
+impl<T> Default for RadioGroup<T> {
+    fn default() -> Self {
+        Self {
+            label: None,
+            options: vec![],
+            selected: None,
+            orientation: Orientation::Vertical,
+            spacing: 10,
+            widget_name: String::new(),
+            label_widget: String::new(),
+            help_text: None,
+            description_widget: String::from("description"),
+            tooltip: None,
+            sensitive: true,
+            read_only: false,
+            on_changed: Callback::default(),
+            on_selection: Callback::default(),
+            prop_selected: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum RadioGroupMessage {
+    Select { index: usize, active: bool },
+    // Sent by the buttons' and the layout's realize handlers
+    #[doc(hidden)]
+    Realized,
+}
+
+impl<T: Clone + Debug + PartialEq + Unpin + 'static> Component for RadioGroup<T> {
+    type Message = RadioGroupMessage;
+    type Properties = Self;
+
+    fn create(props: Self::Properties) -> Self {
+        let mut component = props;
+        component.prop_selected = component.selected.clone();
+        component
+    }
+
+    fn change(&mut self, props: Self::Properties) -> UpdateAction<Self> {
+        let selected = self.selected.take();
+        let prop_selected = self.prop_selected.take();
+
+        *self = props;
+        self.prop_selected = self.selected.clone();
+
+        // Keep the user's choice unless the parent passes a different selection
+        if self.selected == prop_selected {
+            self.selected = selected;
+        }
+
+        UpdateAction::Render
+    }
+
+    fn update(&mut self, msg: Self::Message) -> UpdateAction<Self> {
+        match msg {
+            RadioGroupMessage::Select { index, active } => {
+                // Buttons being deactivated by the group also fire `toggled`
+                if !active {
+                    return UpdateAction::None;
+                }
+
+                match self.options.get(index) {
+                    // Puts the selected button back
+                    Some(option) if self.selected.as_ref() != Some(&option.value) && self.read_only => {
+                        UpdateAction::Render
+                    }
+                    Some(option) if self.selected.as_ref() != Some(&option.value) => {
+                        self.selected = Some(option.value.clone());
+                        self.on_changed.send(option.value.clone());
+                        self.on_selection.send(Selection {
+                            indices: vec![index],
+                            values: vec![option.value.clone()],
+                        });
+                        UpdateAction::Render
+                    }
+                    _ => UpdateAction::None,
+                }
+            }
+            RadioGroupMessage::Realized => UpdateAction::None,
+        }
+    }
+
+    fn view(&self) -> VNode<Self> {
+        self.labeled(gtk! {
+            <Box orientation=self.orientation spacing=self.spacing widget_name=self.widget_name.clone()>
+                <Box visible=false no_show_all=true>
+                    <RadioButton active=self.selected.is_none() />
+                </Box>
+            {
+                self.options.iter().enumerate().map(|(index, option)| {
+                    let active = self.selected.as_ref() == Some(&option.value);
+                    gtk! {
+                        <Box orientation=Orientation::Vertical>
+                            <RadioButton label=option.label.clone() active=active
+                                on realize=|button| {
+                                    join_group(button, active);
+                                    RadioGroupMessage::Realized
+                                }
+                                on toggled=|button| RadioGroupMessage::Select { index, active: button.get_active() } />
+                            {
+                                gtk_if!(option.description.is_some() => {
+                                    <Label label=option.description.clone().unwrap() widget_name=self.description_widget.clone()
+                                        halign=Align::Start />
+                                })
+                            }
+                        </Box>
+                    }
+                })
+            }
+            </Box>
+        })
+    }
+}
+
+impl<T: Clone + Debug + PartialEq + Unpin + 'static> LabeledField for RadioGroup<T> {
+    fn layout(&self) -> FieldLayout {
+        FieldLayout {
+            label: self.label.clone(),
+            orientation: Orientation::Vertical,
+            spacing: self.spacing,
+            label_widget: self.label_widget.clone(),
+            help_text: self.help_text.clone(),
+            tooltip: self.tooltip.clone(),
+            sensitive: self.sensitive,
+            ..FieldLayout::default()
+        }
+    }
+
+    fn realized() -> Self::Message {
+        RadioGroupMessage::Realized
+    }
+}
+
+// vgtk builds every RadioButton on its own, so they get grouped with the hidden button once
+// they're in the widget tree. Joining a group deactivates the button, so the selection is
+// applied again afterwards; the `toggled` signal it fires is ignored as the value is already
+// selected.
+fn join_group(button: &RadioButton, active: bool) {
+    let options = button
+        .get_parent()
+        .and_then(|option| option.get_parent())
+        .and_then(|options| options.downcast::<Container>().ok());
+
+    if let Some(options) = options {
+        let first = options
+            .get_children()
+            .into_iter()
+            .filter_map(|option| option.downcast::<Container>().ok())
+            .filter_map(|option| option.get_children().into_iter().next())
+            .filter_map(|child| child.downcast::<RadioButton>().ok())
+            .next();
+
+        if let Some(first) = first {
+            if &first != button {
+                button.join_group(Some(&first));
+                if active {
+                    button.set_active(true);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+
+use vgtk::lib::gio;
+use vgtk::lib::glib;
+use vgtk::lib::gtk::prelude::*;
+use vgtk::lib::gtk::{RecentData, RecentManager};
+
+/// Most-recently-used paths, kept in the user's `gtk::RecentManager` under an application
+/// defined group so they don't mix with other applications' entries.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecentFiles {
+    pub group: String,
+    // Maximum number of paths returned by `load`
+    pub limit: usize,
+}
+
+impl RecentFiles {
+    pub fn new(group: &str, limit: usize) -> Self {
+        Self {
+            group: group.to_string(),
+            limit,
+        }
+    }
+
+    /// Returns the group's paths, newest first. Entries whose files no longer exist are removed
+    /// from the recent manager on the way.
+    pub fn load(&self) -> Vec<PathBuf> {
+        let manager = match RecentManager::get_default() {
+            Some(manager) => manager,
+            None => return vec![],
+        };
+
+        let mut items: Vec<_> = manager
+            .get_items()
+            .into_iter()
+            .filter(|item| item.has_group(&self.group))
+            .filter(|item| {
+                if item.exists() {
+                    return true;
+                }
+                if let Some(uri) = item.get_uri() {
+                    let _ = manager.remove_item(&uri);
+                }
+                false
+            })
+            .collect();
+        items.sort_by_key(|item| std::cmp::Reverse(item.get_modified()));
+
+        items
+            .iter()
+            .filter_map(|item| item.get_uri())
+            .filter_map(|uri| glib::filename_from_uri(&uri).ok())
+            .map(|(path, _)| path)
+            .take(self.limit)
+            .collect()
+    }
+
+    /// Records the path as used just now.
+    pub fn add(&self, path: &Path) {
+        let manager = match RecentManager::get_default() {
+            Some(manager) => manager,
+            None => return,
+        };
+        let uri = match glib::filename_to_uri(path, None) {
+            Ok(uri) => uri,
+            Err(_) => return,
+        };
+
+        let (content_type, _) = gio::content_type_guess(Some(&path.to_string_lossy()), &[]);
+        let app_name = glib::get_prgname().unwrap_or_else(|| String::from("vgtk"));
+        let data = RecentData {
+            display_name: None,
+            description: None,
+            mime_type: gio::content_type_get_mime_type(&content_type)
+                .map(|mime_type| mime_type.to_string())
+                .unwrap_or_else(|| String::from("application/octet-stream")),
+            app_exec: format!("{} %u", app_name),
+            app_name,
+            groups: vec![self.group.clone()],
+            is_private: true,
+        };
+        manager.add_full(&uri, &data);
+    }
+}
@@ -0,0 +1,780 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use serde_json::{Map, Value};
+use vgtk::lib::gtk::prelude::*;
+use vgtk::lib::gtk::*;
+use vgtk::{gtk, gtk_if, Callback, Component, UpdateAction, VNode};
+
+use crate::checkbox::{CheckBox, CheckState};
+use crate::date::{Date, DateInput};
+use crate::dropdown::Dropdown;
+use crate::passwordbox::PasswordBox;
+use crate::textbox::TextBox;
+
+/// Parsed form of the parts of a JSON Schema that `SchemaForm` understands.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SchemaNode {
+    Object {
+        title: Option<String>,
+        properties: Vec<(String, SchemaNode)>,
+        required: Vec<String>,
+    },
+    Array {
+        title: Option<String>,
+        items: Box<SchemaNode>,
+    },
+    String {
+        title: Option<String>,
+        // Only "password" and "date" change the input
+        format: Option<String>,
+    },
+    Number {
+        title: Option<String>,
+        integer: bool,
+    },
+    Boolean {
+        title: Option<String>,
+    },
+    Enum {
+        title: Option<String>,
+        values: Vec<Value>,
+    },
+    // Types without an input, their values are kept as they are
+    Unsupported,
+}
+
+impl SchemaNode {
+    pub fn parse(schema: &Value) -> Self {
+        let title = schema.get("title").and_then(Value::as_str).map(ToString::to_string);
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            return SchemaNode::Enum {
+                title,
+                values: values.clone(),
+            };
+        }
+
+        // `"type": ["string", "null"]` is treated as "string"
+        let schema_type = match schema.get("type") {
+            Some(Value::String(schema_type)) => Some(schema_type.as_str()),
+            Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).find(|schema_type| *schema_type != "null"),
+            _ if schema.get("properties").is_some() => Some("object"),
+            _ => None,
+        };
+        match schema_type {
+            Some("object") => SchemaNode::Object {
+                title,
+                properties: schema
+                    .get("properties")
+                    .and_then(Value::as_object)
+                    .map(|properties| {
+                        properties
+                            .iter()
+                            .map(|(key, property)| (key.clone(), SchemaNode::parse(property)))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                required: schema
+                    .get("required")
+                    .and_then(Value::as_array)
+                    .map(|required| required.iter().filter_map(Value::as_str).map(ToString::to_string).collect())
+                    .unwrap_or_default(),
+            },
+            Some("array") => SchemaNode::Array {
+                title,
+                items: Box::new(schema.get("items").map_or(SchemaNode::Unsupported, SchemaNode::parse)),
+            },
+            Some("string") => SchemaNode::String {
+                title,
+                format: schema.get("format").and_then(Value::as_str).map(ToString::to_string),
+            },
+            Some("number") => SchemaNode::Number { title, integer: false },
+            Some("integer") => SchemaNode::Number { title, integer: true },
+            Some("boolean") => SchemaNode::Boolean { title },
+            _ => SchemaNode::Unsupported,
+        }
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        match self {
+            SchemaNode::Object { title, .. }
+            | SchemaNode::Array { title, .. }
+            | SchemaNode::String { title, .. }
+            | SchemaNode::Number { title, .. }
+            | SchemaNode::Boolean { title }
+            | SchemaNode::Enum { title, .. } => title.as_deref(),
+            SchemaNode::Unsupported => None,
+        }
+    }
+
+    /// Value for a node without one, the schema's `default` is read by `defaults`.
+    fn empty_value(&self) -> Value {
+        match self {
+            SchemaNode::Object { properties, .. } => Value::Object(
+                properties
+                    .iter()
+                    .map(|(key, property)| (key.clone(), property.empty_value()))
+                    .collect(),
+            ),
+            SchemaNode::Array { .. } => Value::Array(vec![]),
+            SchemaNode::String { .. } => Value::String(String::new()),
+            SchemaNode::Boolean { .. } => Value::Bool(false),
+            SchemaNode::Number { .. } | SchemaNode::Enum { .. } | SchemaNode::Unsupported => Value::Null,
+        }
+    }
+}
+
+/// The schema's `default`s, filled up with empty values where there are none.
+pub fn defaults(schema: &Value) -> Value {
+    let explicit = schema.get("default");
+    let mut value = explicit.cloned().unwrap_or_else(|| SchemaNode::parse(schema).empty_value());
+    if let (Value::Object(values), Some(properties)) = (&mut value, schema.get("properties").and_then(Value::as_object)) {
+        for (key, property) in properties {
+            // An object's own default wins over those of its properties
+            if explicit.and_then(|explicit| explicit.get(key)).is_none() {
+                values.insert(key.clone(), defaults(property));
+            }
+        }
+    }
+    value
+}
+
+/// A step into a JSON value.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+fn value_at<'a>(value: &'a Value, path: &[PathSegment]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, segment| match segment {
+        PathSegment::Key(key) => value.get(key),
+        PathSegment::Index(index) => value.get(index),
+    })
+}
+
+// Objects on the way are created, array items have to exist already
+fn value_at_mut<'a>(value: &'a mut Value, path: &[PathSegment]) -> Option<&'a mut Value> {
+    path.iter().try_fold(value, |value, segment| match segment {
+        PathSegment::Key(key) => {
+            if !value.is_object() {
+                *value = Value::Object(Map::new());
+            }
+            value.as_object_mut().map(|object| object.entry(key.clone()).or_insert(Value::Null))
+        }
+        PathSegment::Index(index) => value.get_mut(index),
+    })
+}
+
+fn with_segment(path: &[PathSegment], segment: PathSegment) -> Vec<PathSegment> {
+    let mut path = path.to_vec();
+    path.push(segment);
+    path
+}
+
+fn enum_label(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Builds inputs for a JSON Schema at runtime and edits a `serde_json::Value` matching it.
+#[derive(Clone, Debug)]
+pub struct SchemaForm {
+    pub schema: Value,
+    // Starting value, the schema's defaults when `Null`
+    pub value: Value,
+    pub submit_label: Option<String>,
+    pub add_label: String,
+    pub remove_label: String,
+    pub required_text: String,
+    pub spacing: i32,
+    pub widget_name: String,
+    pub title_widget: String,
+    pub error_widget: String,
+    pub on_changed: Callback<Value>,
+    pub on_submit: Callback<Value>,
+    root: SchemaNode,
+    current: Value,
+    // Input that doesn't make a value yet, like "1." or a date without a day, by path
+    number_drafts: HashMap<Vec<PathSegment>, String>,
+    date_drafts: HashMap<Vec<PathSegment>, Date>,
+    // Errors are only shown for fields the user changed, or for all of them after a submit, like
+    // in `Form`
+    touched: HashSet<Vec<PathSegment>>,
+    submitted: bool,
+    // Last values given by the parent, to tell whether `current` is still up to date
+    prop_schema: Value,
+    prop_value: Value,
+}
+
+impl Default for SchemaForm {
+    fn default() -> Self {
+        Self {
+            schema: Value::Null,
+            value: Value::Null,
+            submit_label: None,
+            add_label: String::from("Add"),
+            remove_label: String::from("Remove"),
+            required_text: String::from("is required"),
+            spacing: 10,
+            widget_name: String::from("schema-form"),
+            title_widget: String::from("title"),
+            error_widget: String::from("error"),
+            on_changed: Callback::default(),
+            on_submit: Callback::default(),
+            root: SchemaNode::Unsupported,
+            current: Value::Null,
+            number_drafts: HashMap::new(),
+            date_drafts: HashMap::new(),
+            touched: HashSet::new(),
+            submitted: false,
+            prop_schema: Value::Null,
+            prop_value: Value::Null,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum SchemaFormMessage {
+    Set { path: Vec<PathSegment>, value: Value },
+    SetNumber { path: Vec<PathSegment>, text: String, integer: bool },
+    SetDate { path: Vec<PathSegment>, date: Date },
+    AddItem { path: Vec<PathSegment> },
+    RemoveItem { path: Vec<PathSegment>, index: usize },
+    Submit,
+}
+
+impl SchemaForm {
+    pub fn current_value(&self) -> &Value {
+        &self.current
+    }
+
+    fn reset(&mut self) {
+        self.root = SchemaNode::parse(&self.schema);
+        self.current = if self.value.is_null() {
+            defaults(&self.schema)
+        } else {
+            self.value.clone()
+        };
+        self.prop_schema = self.schema.clone();
+        self.prop_value = self.value.clone();
+        self.number_drafts.clear();
+        self.date_drafts.clear();
+        self.touched.clear();
+        self.submitted = false;
+    }
+
+    fn set(&mut self, path: &[PathSegment], value: Value) -> UpdateAction<Self> {
+        if value_at(&self.current, path) == Some(&value) {
+            return UpdateAction::None;
+        }
+        self.touched.insert(path.to_vec());
+        if let Some(target) = value_at_mut(&mut self.current, path) {
+            *target = value;
+        }
+        self.on_changed.send(self.current.clone());
+        UpdateAction::Render
+    }
+
+    /// Required properties left empty, by title.
+    pub fn errors(&self) -> Vec<String> {
+        self.path_errors().into_iter().map(|(_, error)| error).collect()
+    }
+
+    fn path_errors(&self) -> Vec<(Vec<PathSegment>, String)> {
+        let mut errors = vec![];
+        collect_errors(&self.root, Some(&self.current), &[], &self.required_text, &mut errors);
+        errors
+    }
+
+    // An untouched form isn't full of errors
+    fn shown_errors(&self) -> Vec<String> {
+        self.path_errors()
+            .into_iter()
+            .filter(|(path, _)| self.submitted || self.touched.contains(path))
+            .map(|(_, error)| error)
+            .collect()
+    }
+
+    // The schema's `description` of the value at `path`
+    fn tooltip(&self, path: &[PathSegment]) -> Option<String> {
+        value_at(&self.schema, &schema_path(path))
+            .and_then(|schema| schema.get("description"))
+            .and_then(Value::as_str)
+            .map(ToString::to_string)
+    }
+
+    fn node_view(&self, node: &SchemaNode, path: Vec<PathSegment>, label: Option<String>) -> VNode<Self> {
+        let value = value_at(&self.current, &path).cloned().unwrap_or(Value::Null);
+        let tooltip = self.tooltip(&path);
+        match node {
+            SchemaNode::Object { properties, .. } => gtk! {
+                <Box orientation=Orientation::Vertical spacing=self.spacing property_tooltip_text=tooltip>
+                    {
+                        gtk_if!(label.is_some() => {
+                            <Label label=label.clone().unwrap_or_default() widget_name=self.title_widget.clone() halign=Align::Start />
+                        })
+                    }
+                    {
+                        properties.iter().map(|(key, property)| {
+                            let label = property.title().unwrap_or(key).to_string();
+                            self.node_view(property, with_segment(&path, PathSegment::Key(key.clone())), Some(label))
+                        })
+                    }
+                </Box>
+            },
+            SchemaNode::Array { items, .. } => {
+                let count = value.as_array().map_or(0, Vec::len);
+                let add_path = path.clone();
+                gtk! {
+                    <Box orientation=Orientation::Vertical spacing=self.spacing property_tooltip_text=tooltip>
+                        {
+                            gtk_if!(label.is_some() => {
+                                <Label label=label.clone().unwrap_or_default() widget_name=self.title_widget.clone() halign=Align::Start />
+                            })
+                        }
+                        {
+                            (0..count).map(|index| {
+                                let remove_path = path.clone();
+                                gtk! {
+                                    <Box orientation=Orientation::Horizontal spacing=self.spacing>
+                                        { self.node_view(items, with_segment(&path, PathSegment::Index(index)), None) }
+                                        <Button label=self.remove_label.clone() valign=Align::Start Box::pack_type=PackType::End
+                                            on clicked=|_| SchemaFormMessage::RemoveItem { path: remove_path.clone(), index } />
+                                    </Box>
+                                }
+                            })
+                        }
+                        <Box orientation=Orientation::Horizontal>
+                            <Button label=self.add_label.clone() on clicked=|_| SchemaFormMessage::AddItem { path: add_path.clone() } />
+                        </Box>
+                    </Box>
+                }
+            }
+            SchemaNode::String { format, .. } => match format.as_deref() {
+                Some("password") => gtk! {
+                    <@PasswordBox label=label text=value.as_str().unwrap_or_default().to_string() tooltip=tooltip
+                        on_changed=|text| SchemaFormMessage::Set { path: path.clone(), value: Value::String(text) } />
+                },
+                Some("date") => {
+                    let parsed = value.as_str().and_then(|text| NaiveDate::parse_from_str(text, "%Y-%m-%d").ok());
+                    let date = match self.date_drafts.get(&path) {
+                        Some(draft) if draft.to_naive_date() == parsed => draft.clone(),
+                        _ => parsed.map(Date::from).unwrap_or_default(),
+                    };
+                    gtk! {
+                        <@DateInput label=label date=date tooltip=tooltip
+                            on_update=|date| SchemaFormMessage::SetDate { path: path.clone(), date } />
+                    }
+                }
+                _ => gtk! {
+                    <@TextBox label=label text=value.as_str().unwrap_or_default().to_string() tooltip=tooltip
+                        on_changed=|text| SchemaFormMessage::Set { path: path.clone(), value: Value::String(text) } />
+                },
+            },
+            SchemaNode::Number { integer, .. } => {
+                let integer = *integer;
+                let text = match self.number_drafts.get(&path) {
+                    Some(draft) if parse_number(draft, integer).map_or(false, |number| number == value) => draft.clone(),
+                    _ if value.is_null() => String::new(),
+                    _ => value.to_string(),
+                };
+                gtk! {
+                    <@TextBox label=label text=text tooltip=tooltip
+                        on_changed=|text| SchemaFormMessage::SetNumber { path: path.clone(), text, integer } />
+                }
+            }
+            SchemaNode::Boolean { .. } => gtk! {
+                <@CheckBox label=label state=CheckState::from(value.as_bool().unwrap_or(false)) tooltip=tooltip
+                    on_toggled=|state| SchemaFormMessage::Set { path: path.clone(), value: Value::Bool(state.is_checked()) } />
+            },
+            SchemaNode::Enum { values, .. } => {
+                let items: Vec<String> = values.iter().map(enum_label).collect();
+                let selected = if value.is_null() { None } else { Some(enum_label(&value)) };
+                let values = values.clone();
+                gtk! {
+                    <@Dropdown label=label items=items selected=selected tooltip=tooltip
+                        on_select=|selected| SchemaFormMessage::Set {
+                            path: path.clone(),
+                            value: selected
+                                .and_then(|selected| values.iter().find(|value| enum_label(value) == selected).cloned())
+                                .unwrap_or(Value::Null),
+                        } />
+                }
+            }
+            SchemaNode::Unsupported => gtk! {
+                <Box visible=false no_show_all=true />
+            },
+        }
+    }
+}
+
+// An empty text is `Null`, `None` means the text isn't a number (yet)
+fn parse_number(text: &str, integer: bool) -> Option<Value> {
+    let text = text.trim();
+    if text.is_empty() {
+        Some(Value::Null)
+    } else if integer {
+        text.parse::<i64>().ok().map(Value::from)
+    } else {
+        text.parse::<f64>().ok().and_then(serde_json::Number::from_f64).map(Value::Number)
+    }
+}
+
+fn collect_errors(
+    node: &SchemaNode,
+    value: Option<&Value>,
+    path: &[PathSegment],
+    required_text: &str,
+    errors: &mut Vec<(Vec<PathSegment>, String)>,
+) {
+    match node {
+        SchemaNode::Object {
+            properties, required, ..
+        } => {
+            for (key, property) in properties {
+                let property_path = with_segment(path, PathSegment::Key(key.clone()));
+                let property_value = value.and_then(|value| value.get(key));
+                let empty = match property_value {
+                    None | Some(Value::Null) => true,
+                    Some(Value::String(text)) => text.is_empty(),
+                    Some(_) => false,
+                };
+                if empty && required.contains(key) {
+                    let error = format!("{} {}", property.title().unwrap_or(key), required_text);
+                    errors.push((property_path.clone(), error));
+                }
+                collect_errors(property, property_value, &property_path, required_text, errors);
+            }
+        }
+        SchemaNode::Array { items, .. } => {
+            for (index, item) in value.and_then(Value::as_array).into_iter().flatten().enumerate() {
+                let item_path = with_segment(path, PathSegment::Index(index));
+                collect_errors(items, Some(item), &item_path, required_text, errors);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Component for SchemaForm {
+    type Message = SchemaFormMessage;
+    type Properties = Self;
+
+    fn create(props: Self::Properties) -> Self {
+        let mut component = props;
+        component.reset();
+        component
+    }
+
+    fn change(&mut self, props: Self::Properties) -> UpdateAction<Self> {
+        let root = std::mem::replace(&mut self.root, SchemaNode::Unsupported);
+        let current = self.current.take();
+        let (prop_schema, prop_value) = (self.prop_schema.take(), self.prop_value.take());
+        let number_drafts = std::mem::take(&mut self.number_drafts);
+        let date_drafts = std::mem::take(&mut self.date_drafts);
+        let touched = std::mem::take(&mut self.touched);
+        let submitted = self.submitted;
+
+        *self = props;
+        if self.schema == prop_schema && self.value == prop_value {
+            self.root = root;
+            self.current = current;
+            self.prop_schema = prop_schema;
+            self.prop_value = prop_value;
+            self.number_drafts = number_drafts;
+            self.date_drafts = date_drafts;
+            self.touched = touched;
+            self.submitted = submitted;
+        } else {
+            self.reset();
+        }
+
+        UpdateAction::Render
+    }
+
+    fn update(&mut self, msg: Self::Message) -> UpdateAction<Self> {
+        match msg {
+            SchemaFormMessage::Set { path, value } => self.set(&path, value),
+            SchemaFormMessage::SetNumber { path, text, integer } => match parse_number(&text, integer) {
+                Some(value) => {
+                    self.number_drafts.insert(path.clone(), text);
+                    self.set(&path, value)
+                }
+                // Keeps the last number until the text is one again
+                None => UpdateAction::None,
+            },
+            SchemaFormMessage::SetDate { path, date } => {
+                // Incomplete dates are left out of the value
+                let value = date
+                    .to_naive_date()
+                    .map_or(Value::Null, |date| Value::String(date.format("%Y-%m-%d").to_string()));
+                self.date_drafts.insert(path.clone(), date);
+                self.set(&path, value)
+            }
+            SchemaFormMessage::AddItem { path } => {
+                let item_schema = value_at(&self.schema, &schema_path(&path)).and_then(|array| array.get("items"));
+                let item = item_schema.map_or(Value::Null, defaults);
+                if let Some(target) = value_at_mut(&mut self.current, &path) {
+                    match target {
+                        Value::Array(items) => items.push(item),
+                        target => *target = Value::Array(vec![item]),
+                    }
+                }
+                self.on_changed.send(self.current.clone());
+                UpdateAction::Render
+            }
+            SchemaFormMessage::RemoveItem { path, index } => {
+                if let Some(Value::Array(items)) = value_at_mut(&mut self.current, &path) {
+                    if index < items.len() {
+                        items.remove(index);
+                    }
+                }
+                // Drafts and touched fields are kept by index and would end up on the wrong items
+                self.number_drafts.clear();
+                self.date_drafts.clear();
+                self.touched.retain(|touched| !touched.starts_with(&path));
+                self.on_changed.send(self.current.clone());
+                UpdateAction::Render
+            }
+            SchemaFormMessage::Submit => {
+                // Submitting shows the errors of every field
+                self.submitted = true;
+                if self.errors().is_empty() {
+                    self.on_submit.send(self.current.clone());
+                }
+                UpdateAction::Render
+            }
+        }
+    }
+
+    fn view(&self) -> VNode<Self> {
+        let errors = self.shown_errors();
+        let title = self.root.title().map(ToString::to_string);
+        gtk! {
+            <Box orientation=Orientation::Vertical spacing=self.spacing widget_name=self.widget_name.clone()>
+                { self.node_view(&self.root, vec![], title) }
+                {
+                    errors.iter().map(|error| gtk! {
+                        <Label label=error.clone() widget_name=self.error_widget.clone() halign=Align::Start />
+                    })
+                }
+                {
+                    gtk_if!(self.submit_label.is_some() => {
+                        <Box orientation=Orientation::Horizontal>
+                            <Button label=self.submit_label.clone().unwrap_or_default() Box::pack_type=PackType::End
+                                on clicked=|_| SchemaFormMessage::Submit />
+                        </Box>
+                    })
+                }
+            </Box>
+        }
+    }
+}
+
+// Where the schema of the value at `path` is, array indices all share the `items` schema
+fn schema_path(path: &[PathSegment]) -> Vec<PathSegment> {
+    let mut schema_path = vec![];
+    for segment in path {
+        match segment {
+            PathSegment::Key(key) => {
+                schema_path.push(PathSegment::Key(String::from("properties")));
+                schema_path.push(PathSegment::Key(key.clone()));
+            }
+            PathSegment::Index(_) => schema_path.push(PathSegment::Key(String::from("items"))),
+        }
+    }
+    schema_path
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn key(key: &str) -> PathSegment {
+        PathSegment::Key(key.to_string())
+    }
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "title": "Plugin",
+            "required": ["name"],
+            "properties": {
+                "name": { "type": "string", "title": "Name" },
+                "port": { "type": "integer", "default": 8080 },
+                "ratio": { "type": ["number", "null"] },
+                "level": { "enum": ["low", "high"], "type": "string" },
+                "owner": {
+                    "properties": {
+                        "email": { "type": "string", "format": "email" },
+                        "admin": { "type": "boolean" }
+                    },
+                    "required": ["email"]
+                },
+                "tags": { "type": "array", "items": { "type": "string" } }
+            }
+        })
+    }
+
+    #[test]
+    fn parse_reads_supported_types() {
+        let properties = match SchemaNode::parse(&schema()) {
+            SchemaNode::Object {
+                title,
+                properties,
+                required,
+            } => {
+                assert_eq!(title.as_deref(), Some("Plugin"));
+                assert_eq!(required, vec![String::from("name")]);
+                properties
+            }
+            node => panic!("not an object: {:?}", node),
+        };
+        let node = |name: &str| properties.iter().find(|(key, _)| key == name).map(|(_, node)| node.clone()).unwrap();
+
+        assert_eq!(node("name"), SchemaNode::String { title: Some(String::from("Name")), format: None });
+        assert_eq!(node("port"), SchemaNode::Number { title: None, integer: true });
+        assert_eq!(node("ratio"), SchemaNode::Number { title: None, integer: false });
+        assert_eq!(node("level"), SchemaNode::Enum { title: None, values: vec![json!("low"), json!("high")] });
+        assert_eq!(
+            node("tags"),
+            SchemaNode::Array {
+                title: None,
+                items: Box::new(SchemaNode::String { title: None, format: None }),
+            }
+        );
+        // Objects can leave out the type
+        assert_eq!(
+            node("owner"),
+            SchemaNode::Object {
+                title: None,
+                properties: vec![
+                    (String::from("email"), SchemaNode::String { title: None, format: Some(String::from("email")) }),
+                    (String::from("admin"), SchemaNode::Boolean { title: None }),
+                ],
+                required: vec![String::from("email")],
+            }
+        );
+        assert_eq!(SchemaNode::parse(&json!({ "type": "null" })), SchemaNode::Unsupported);
+    }
+
+    #[test]
+    fn defaults_fill_up_missing_values() {
+        assert_eq!(
+            defaults(&schema()),
+            json!({
+                "name": "",
+                "port": 8080,
+                "ratio": null,
+                "level": null,
+                "owner": { "email": "", "admin": false },
+                "tags": []
+            })
+        );
+
+        // An object's default wins over its properties' ones
+        let schema = json!({
+            "type": "object",
+            "default": { "port": 1 },
+            "properties": {
+                "port": { "type": "integer", "default": 8080 },
+                "host": { "type": "string", "default": "localhost" }
+            }
+        });
+        assert_eq!(defaults(&schema), json!({ "port": 1, "host": "localhost" }));
+    }
+
+    #[test]
+    fn collect_errors_reports_empty_required_properties() {
+        let node = SchemaNode::parse(&schema());
+        let mut errors = vec![];
+        collect_errors(&node, Some(&defaults(&schema())), &[], "is required", &mut errors);
+        assert_eq!(
+            errors,
+            vec![
+                (vec![key("name")], String::from("Name is required")),
+                (vec![key("owner"), key("email")], String::from("email is required")),
+            ]
+        );
+
+        let mut errors = vec![];
+        let value = json!({ "name": "Sync", "port": 0, "owner": { "email": "a@b.c" } });
+        collect_errors(&node, Some(&value), &[], "is required", &mut errors);
+        assert!(errors.is_empty());
+
+        // Every array item is checked against `items`
+        let node = SchemaNode::parse(&json!({
+            "type": "array",
+            "items": { "type": "object", "required": ["id"], "properties": { "id": { "type": "integer" } } }
+        }));
+        let mut errors = vec![];
+        collect_errors(&node, Some(&json!([{ "id": 1 }, { "id": null }])), &[], "is required", &mut errors);
+        assert_eq!(errors, vec![(vec![PathSegment::Index(1), key("id")], String::from("id is required"))]);
+    }
+
+    #[test]
+    fn parse_number_tells_empty_from_invalid() {
+        assert_eq!(parse_number(" 42 ", true), Some(json!(42)));
+        assert_eq!(parse_number("1.5", false), Some(json!(1.5)));
+        assert_eq!(parse_number("", true), Some(Value::Null));
+        assert_eq!(parse_number("1.5", true), None);
+        assert_eq!(parse_number("1.", true), None);
+        assert_eq!(parse_number("abc", false), None);
+        assert_eq!(parse_number("NaN", false), None);
+    }
+
+    #[test]
+    fn bad_numbers_keep_the_last_value() {
+        let mut form = SchemaForm::create(SchemaForm {
+            schema: schema(),
+            ..SchemaForm::default()
+        });
+        let path = vec![key("port")];
+        let set_number = |text: &str| SchemaFormMessage::SetNumber {
+            path: path.clone(),
+            text: text.to_string(),
+            integer: true,
+        };
+
+        assert!(matches!(form.update(set_number("80")), UpdateAction::Render));
+        assert!(matches!(form.update(set_number("80x")), UpdateAction::None));
+        assert_eq!(form.current_value()["port"], json!(80));
+        assert_eq!(form.number_drafts.get(&path).map(String::as_str), Some("80"));
+    }
+
+    #[test]
+    fn schema_path_goes_through_properties_and_items() {
+        assert_eq!(
+            schema_path(&[key("owner"), key("email")]),
+            vec![key("properties"), key("owner"), key("properties"), key("email")]
+        );
+        assert_eq!(
+            schema_path(&[key("tags"), PathSegment::Index(3)]),
+            vec![key("properties"), key("tags"), key("items")]
+        );
+        assert_eq!(
+            value_at(&schema(), &schema_path(&[key("tags"), PathSegment::Index(0)])),
+            Some(&json!({ "type": "string" }))
+        );
+    }
+
+    #[test]
+    fn value_at_reads_and_writes_nested_values() {
+        let mut value = json!({ "owner": { "email": "a@b.c" }, "tags": ["x", "y"] });
+        assert_eq!(value_at(&value, &[key("owner"), key("email")]), Some(&json!("a@b.c")));
+        assert_eq!(value_at(&value, &[key("tags"), PathSegment::Index(1)]), Some(&json!("y")));
+        assert_eq!(value_at(&value, &[key("tags"), PathSegment::Index(2)]), None);
+        assert_eq!(value_at(&value, &[key("missing"), key("email")]), None);
+
+        *value_at_mut(&mut value, &[key("tags"), PathSegment::Index(0)]).unwrap() = json!("z");
+        // Missing objects on the way are created
+        *value_at_mut(&mut value, &[key("limits"), key("cpu")]).unwrap() = json!(2);
+        assert!(value_at_mut(&mut value, &[key("tags"), PathSegment::Index(5)]).is_none());
+        assert_eq!(value, json!({ "owner": { "email": "a@b.c" }, "tags": ["z", "y"], "limits": { "cpu": 2 } }));
+    }
+}
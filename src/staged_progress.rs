@@ -0,0 +1,180 @@
+use vgtk::lib::gtk::prelude::*;
+use vgtk::lib::gtk::*;
+use vgtk::{gtk, gtk_if, Component, UpdateAction, VNode};
+
+use crate::progress_bar::{format_template, ProgressBar};
+
+/// A named step of a `StagedProgress`. The weight sets how much of the overall bar it takes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stage {
+    pub name: String,
+    pub weight: f64,
+}
+
+impl Stage {
+    pub fn new(name: &str, weight: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            weight,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StageStatus {
+    Done,
+    Current,
+    Pending,
+}
+
+#[derive(Clone, Debug)]
+pub struct StagedProgress {
+    pub stages: Vec<Stage>,
+    // Index of the running stage, `stages.len()` once everything is done
+    pub current: usize,
+    // Progress within the current stage
+    pub stage_fraction: f64,
+    // Text on the bar, with `stage`, `step`, `steps` and `stage_percent` on top of the
+    // placeholders of `ProgressBar::text_format`
+    pub text_format: String,
+    pub show_steps: bool,
+    pub done_marker: String,
+    pub current_marker: String,
+    pub pending_marker: String,
+    pub spacing: i32,
+    pub widget_name: String,
+    // Step rows are named `<step_widget>-done`, `-current` and `-pending` for styling
+    pub step_widget: String,
+    pub tooltip: Option<String>,
+    pub sensitive: bool,
+    // Lets the step names be selected and copied, passed on to the bar
+    pub read_only: bool,
+}
+
+impl Default for StagedProgress {
+    fn default() -> Self {
+        Self {
+            stages: vec![],
+            current: 0,
+            stage_fraction: 0.0,
+            text_format: String::from("{stage} ({step}/{steps}): {stage_percent}% — {percent}% overall"),
+            show_steps: true,
+            done_marker: String::from("✔"),
+            current_marker: String::from("▶"),
+            pending_marker: String::from("○"),
+            spacing: 10,
+            widget_name: String::new(),
+            step_widget: String::from("step"),
+            tooltip: None,
+            sensitive: true,
+            read_only: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum StagedProgressMessage {}
+
+impl StagedProgress {
+    pub fn status(&self, index: usize) -> StageStatus {
+        if index < self.current {
+            StageStatus::Done
+        } else if index == self.current {
+            StageStatus::Current
+        } else {
+            StageStatus::Pending
+        }
+    }
+
+    /// Progress over all stages, each counting by its weight.
+    pub fn overall_fraction(&self) -> f64 {
+        let total: f64 = self.stages.iter().map(|stage| stage.weight).sum();
+        if total <= 0.0 {
+            return if self.current >= self.stages.len() { 1.0 } else { 0.0 };
+        }
+
+        let done: f64 = self.stages.iter().take(self.current).map(|stage| stage.weight).sum();
+        let current = self
+            .stages
+            .get(self.current)
+            .map_or(0.0, |stage| stage.weight * self.stage_fraction.max(0.0).min(1.0));
+        ((done + current) / total).min(1.0)
+    }
+
+    fn text(&self) -> String {
+        let stage = self.stages.get(self.current).map(|stage| stage.name.clone()).unwrap_or_default();
+        let step = (self.current + 1).min(self.stages.len());
+        format_template(
+            &self.text_format,
+            &[
+                ("stage", stage),
+                ("step", step.to_string()),
+                ("steps", self.stages.len().to_string()),
+                ("stage_percent", format!("{:.0}", self.stage_fraction * 100.0)),
+                ("percent", format!("{:.0}", self.overall_fraction() * 100.0)),
+            ],
+        )
+    }
+
+    fn marker(&self, status: StageStatus) -> String {
+        match status {
+            StageStatus::Done => self.done_marker.clone(),
+            StageStatus::Current => self.current_marker.clone(),
+            StageStatus::Pending => self.pending_marker.clone(),
+        }
+    }
+
+    fn step_name(&self, status: StageStatus) -> String {
+        let suffix = match status {
+            StageStatus::Done => "done",
+            StageStatus::Current => "current",
+            StageStatus::Pending => "pending",
+        };
+        format!("{}-{}", self.step_widget, suffix)
+    }
+}
+
+impl Component for StagedProgress {
+    type Message = StagedProgressMessage;
+    type Properties = Self;
+
+    fn create(props: Self::Properties) -> Self {
+        props
+    }
+
+    fn change(&mut self, props: Self::Properties) -> UpdateAction<Self> {
+        *self = props;
+        UpdateAction::Render
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> UpdateAction<Self> {
+        UpdateAction::None
+    }
+
+    fn view(&self) -> VNode<Self> {
+        gtk! {
+            <Box orientation=Orientation::Vertical spacing=self.spacing sensitive=self.sensitive
+                property_tooltip_text=self.tooltip.clone()>
+                <@ProgressBar progress_fraction=self.overall_fraction() progress_text=self.text() show_text=true
+                    widget_name=self.widget_name.clone() read_only=self.read_only />
+                {
+                    gtk_if!(self.show_steps => {
+                        <Box orientation=Orientation::Vertical spacing=4>
+                            {
+                                self.stages.iter().enumerate().map(|(index, stage)| {
+                                    let status = self.status(index);
+                                    gtk! {
+                                        <Box orientation=Orientation::Horizontal spacing=self.spacing widget_name=self.step_name(status)>
+                                            <Label label=self.marker(status) />
+                                            <Label label=stage.name.clone() halign=Align::Start selectable=self.read_only />
+                                        </Box>
+                                    }
+                                })
+                            }
+                        </Box>
+                    })
+                }
+            </Box>
+        }
+    }
+}
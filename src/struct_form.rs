@@ -0,0 +1,100 @@
+use std::fmt;
+
+use vgtk::{gtk, Callback, Component, UpdateAction, VNode};
+
+use crate::form::{same_fields, Form, FormField, FormValues};
+
+pub use vgtk_components_derive::{FormChoice, VgtkForm};
+
+/// A struct that can be edited with a `Form`, usually implemented with `#[derive(VgtkForm)]`.
+pub trait VgtkForm: Clone + Default + fmt::Debug + 'static {
+    /// The form's fields, filled in from `self`.
+    fn form_fields(&self) -> Vec<FormField>;
+
+    /// Copies the form's values back into the struct. Values that can't be converted, like an
+    /// incomplete date, leave the field as it is.
+    fn apply_form_values(&mut self, values: &FormValues);
+}
+
+/// A fieldless enum shown as a `Dropdown`, usually implemented with `#[derive(FormChoice)]`.
+pub trait FormChoice: Sized {
+    fn choices() -> Vec<String>;
+    fn to_choice(&self) -> String;
+    fn from_choice(choice: &str) -> Option<Self>;
+}
+
+/// Edits a `VgtkForm` struct. `#[derive(VgtkForm)]` also defines a `<Struct>Form` alias for it.
+#[derive(Clone, Debug)]
+pub struct StructForm<T: VgtkForm> {
+    pub value: T,
+    pub submit_label: String,
+    pub submit_requires_changes: bool,
+    pub on_changed: Callback<T>,
+    pub on_submit: Callback<T>,
+    // The value as edited so far, `value` stays what the parent gave
+    edited: T,
+}
+
+impl<T: VgtkForm> Default for StructForm<T> {
+    fn default() -> Self {
+        Self {
+            value: T::default(),
+            submit_label: String::from("Save"),
+            submit_requires_changes: true,
+            on_changed: Callback::default(),
+            on_submit: Callback::default(),
+            edited: T::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum StructFormMessage {
+    Changed { values: FormValues },
+    Submit { values: FormValues },
+}
+
+impl<T: VgtkForm> Component for StructForm<T> {
+    type Message = StructFormMessage;
+    type Properties = Self;
+
+    fn create(props: Self::Properties) -> Self {
+        let mut component = props;
+        component.edited = component.value.clone();
+        component
+    }
+
+    fn change(&mut self, props: Self::Properties) -> UpdateAction<Self> {
+        let edited = self.edited.clone();
+        let same_value = same_fields(&self.value.form_fields(), &props.value.form_fields());
+        *self = props;
+        // A different value from the parent replaces the edits, like it resets the form
+        self.edited = if same_value { edited } else { self.value.clone() };
+        UpdateAction::Render
+    }
+
+    fn update(&mut self, msg: Self::Message) -> UpdateAction<Self> {
+        match msg {
+            StructFormMessage::Changed { values } => {
+                self.edited.apply_form_values(&values);
+                self.on_changed.send(self.edited.clone());
+                UpdateAction::None
+            }
+            StructFormMessage::Submit { values } => {
+                self.edited.apply_form_values(&values);
+                self.on_submit.send(self.edited.clone());
+                UpdateAction::None
+            }
+        }
+    }
+
+    fn view(&self) -> VNode<Self> {
+        // Built from `value` so the form compares its fields against what the parent gave
+        gtk! {
+            <@Form fields=self.value.form_fields() submit_label=self.submit_label.clone()
+                submit_requires_changes=self.submit_requires_changes
+                on_changed=|values| StructFormMessage::Changed { values }
+                on_submit=|values| StructFormMessage::Submit { values } />
+        }
+    }
+}
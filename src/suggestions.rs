@@ -0,0 +1,273 @@
+//! Autocomplete suggestions for `TextBox`.
+
+use std::fmt;
+use std::future::Future;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use vgtk::lib::glib::{self, ThreadPool};
+
+/// Provides suggestions for the text typed so far. The returned future is polled on the glib main
+/// context, so it must not block; see `BlockingSuggestions` for lookups doing blocking I/O.
+pub trait SuggestionSource: fmt::Debug {
+    fn suggest(&self, text: &str) -> Pin<Box<dyn Future<Output = Vec<String>>>>;
+}
+
+/// A fixed list, offering the items starting with the typed text regardless of case.
+#[derive(Clone, Debug)]
+pub struct StaticSuggestions {
+    pub items: Vec<String>,
+    // Most suggestions offered at a time
+    pub limit: usize,
+}
+
+impl Default for StaticSuggestions {
+    fn default() -> Self {
+        Self {
+            items: vec![],
+            limit: 10,
+        }
+    }
+}
+
+impl StaticSuggestions {
+    pub fn new(items: Vec<String>) -> Self {
+        Self {
+            items,
+            ..Self::default()
+        }
+    }
+}
+
+impl SuggestionSource for StaticSuggestions {
+    fn suggest(&self, text: &str) -> Pin<Box<dyn Future<Output = Vec<String>>>> {
+        let suggestions: Vec<String> = self
+            .items
+            .iter()
+            .filter(|item| find_ignore_case(item, text).map_or(false, |range| range.start == 0))
+            .take(self.limit)
+            .cloned()
+            .collect();
+        Box::pin(async move { suggestions })
+    }
+}
+
+/// Suggestions worked out by a function on the main thread, e.g. a lookup in memory.
+pub struct SuggestionFn<F> {
+    suggest: F,
+}
+
+impl<F> SuggestionFn<F>
+where
+    F: Fn(&str) -> Vec<String>,
+{
+    pub fn new(suggest: F) -> Self {
+        Self { suggest }
+    }
+}
+
+impl<F> fmt::Debug for SuggestionFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SuggestionFn").finish()
+    }
+}
+
+impl<F> SuggestionSource for SuggestionFn<F>
+where
+    F: Fn(&str) -> Vec<String>,
+{
+    fn suggest(&self, text: &str) -> Pin<Box<dyn Future<Output = Vec<String>>>> {
+        let suggestions = (self.suggest)(text);
+        Box::pin(async move { suggestions })
+    }
+}
+
+/// Suggestions from a blocking lookup (database query, address book) run on a worker thread so
+/// the UI doesn't freeze. Lookups still waiting for the thread when a newer one comes in are
+/// skipped.
+///
+/// Newer means newer for this source, not for a text box: give every `TextBox` its own source, as
+/// typing in one would otherwise skip the lookups of the others.
+pub struct BlockingSuggestions<F> {
+    suggest: Arc<F>,
+    pool: ThreadPool,
+    // Number of the last lookup asked for
+    latest: Arc<AtomicUsize>,
+}
+
+impl<F> BlockingSuggestions<F>
+where
+    F: Fn(&str) -> Vec<String> + Send + Sync + 'static,
+{
+    pub fn new(suggest: F) -> Self {
+        Self {
+            suggest: Arc::new(suggest),
+            pool: ThreadPool::new_exclusive(1).expect("Failed to create suggestion thread"),
+            latest: Arc::default(),
+        }
+    }
+}
+
+impl<F> fmt::Debug for BlockingSuggestions<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockingSuggestions").finish()
+    }
+}
+
+impl<F> SuggestionSource for BlockingSuggestions<F>
+where
+    F: Fn(&str) -> Vec<String> + Send + Sync + 'static,
+{
+    fn suggest(&self, text: &str) -> Pin<Box<dyn Future<Output = Vec<String>>>> {
+        let suggest = self.suggest.clone();
+        let text = text.to_string();
+        let latest = self.latest.clone();
+        let lookup = latest.fetch_add(1, Ordering::SeqCst) + 1;
+        let run = move || {
+            // The text changed while this one was queued, its suggestions would be dropped anyway
+            if latest.load(Ordering::SeqCst) != lookup {
+                return vec![];
+            }
+            suggest(&text)
+        };
+        match self.pool.push_future(run) {
+            Ok(suggestions) => Box::pin(suggestions),
+            Err(_) => Box::pin(async { vec![] }),
+        }
+    }
+}
+
+/// Byte range of the first occurrence of `pattern` in `text`, ignoring case.
+pub fn find_ignore_case(text: &str, pattern: &str) -> Option<Range<usize>> {
+    if pattern.is_empty() {
+        return None;
+    }
+    text.char_indices().find_map(|(start, _)| {
+        let mut rest = text[start..].char_indices();
+        let mut end = start;
+        for expected in pattern.chars() {
+            let (offset, c) = rest.next()?;
+            if !c.to_lowercase().eq(expected.to_lowercase()) {
+                return None;
+            }
+            end = start + offset + c.len_utf8();
+        }
+        Some(start..end)
+    })
+}
+
+/// Pango markup for a suggestion with the typed text in bold.
+pub fn highlight_match(suggestion: &str, typed: &str) -> String {
+    match find_ignore_case(suggestion, typed) {
+        Some(range) => format!(
+            "{}<b>{}</b>{}",
+            glib::markup_escape_text(&suggestion[..range.start]),
+            glib::markup_escape_text(&suggestion[range.clone()]),
+            glib::markup_escape_text(&suggestion[range.end..]),
+        ),
+        None => glib::markup_escape_text(suggestion).to_string(),
+    }
+}
+
+/// The part of the text being completed: everything after the last separator, or all of it.
+pub fn current_token(text: &str, separator: Option<char>) -> &str {
+    match separator.and_then(|separator| text.rfind(separator).map(|index| index + separator.len_utf8())) {
+        Some(start) => text[start..].trim_start(),
+        None => text,
+    }
+}
+
+/// Replaces the token being completed with the accepted suggestion, keeping the earlier ones.
+pub fn replace_token(text: &str, suggestion: &str, separator: Option<char>) -> String {
+    match separator.and_then(|separator| text.rfind(separator).map(|index| index + separator.len_utf8())) {
+        Some(start) => format!("{} {}", &text[..start], suggestion),
+        None => suggestion.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+    use super::*;
+
+    #[test]
+    fn find_ignore_case_returns_byte_ranges() {
+        assert_eq!(find_ignore_case("Hello World", "wor"), Some(6..9));
+        assert_eq!(find_ignore_case("Hello World", "HELLO"), Some(0..5));
+        assert_eq!(find_ignore_case("ÄBC", "äb"), Some(0..3));
+        assert_eq!(find_ignore_case("naïve café", "CAFÉ"), Some(7..12));
+        assert_eq!(find_ignore_case("Hello", "world"), None);
+        assert_eq!(find_ignore_case("Hel", "hello"), None);
+        assert_eq!(find_ignore_case("Hello", ""), None);
+    }
+
+    #[test]
+    fn current_token_follows_the_last_separator() {
+        assert_eq!(current_token("a@x.com, bo", Some(',')), "bo");
+        assert_eq!(current_token("a@x.com,bo", Some(',')), "bo");
+        assert_eq!(current_token("a@x.com, ", Some(',')), "");
+        assert_eq!(current_token("bo", Some(',')), "bo");
+        assert_eq!(current_token("a@x.com, bo", None), "a@x.com, bo");
+    }
+
+    #[test]
+    fn replace_token_keeps_earlier_tokens() {
+        assert_eq!(replace_token("a@x.com, bo", "bob@y.com", Some(',')), "a@x.com, bob@y.com");
+        assert_eq!(replace_token("a@x.com,bo", "bob@y.com", Some(',')), "a@x.com, bob@y.com");
+        assert_eq!(replace_token("bo", "bob@y.com", Some(',')), "bob@y.com");
+        assert_eq!(replace_token("a@x.com, bo", "bob@y.com", None), "bob@y.com");
+    }
+
+    // The futures of the simple sources are ready right away
+    fn ready<T>(future: Pin<Box<dyn Future<Output = T>>>) -> T {
+        fn raw_waker() -> RawWaker {
+            fn clone(_: *const ()) -> RawWaker {
+                raw_waker()
+            }
+            fn noop(_: *const ()) {}
+            static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+
+        let waker = unsafe { Waker::from_raw(raw_waker()) };
+        let mut future = future;
+        match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(value) => value,
+            Poll::Pending => panic!("the suggestions weren't ready"),
+        }
+    }
+
+    fn items(items: &[&str]) -> Vec<String> {
+        items.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn static_suggestions_start_with_the_typed_text() {
+        let source = StaticSuggestions::new(items(&["Apple", "apricot", "Banana", "pineapple", "Äpfel"]));
+        assert_eq!(source.limit, 10);
+        assert_eq!(ready(source.suggest("ap")), items(&["Apple", "apricot"]));
+        assert_eq!(ready(source.suggest("APP")), items(&["Apple"]));
+        assert_eq!(ready(source.suggest("äp")), items(&["Äpfel"]));
+        assert!(ready(source.suggest("cherry")).is_empty());
+    }
+
+    #[test]
+    fn static_suggestions_respect_the_limit() {
+        let source = StaticSuggestions {
+            limit: 2,
+            ..StaticSuggestions::new(items(&["a1", "b", "a2", "a3"]))
+        };
+        assert_eq!(ready(source.suggest("a")), items(&["a1", "a2"]));
+    }
+
+    #[test]
+    fn highlight_match_escapes_and_bolds_the_match() {
+        assert_eq!(highlight_match("Hello World", "wor"), "Hello <b>Wor</b>ld");
+        assert_eq!(highlight_match("<a&b>", "a&"), "&lt;<b>a&amp;</b>b&gt;");
+        assert_eq!(highlight_match("naïve café", "CAFÉ"), "naïve <b>café</b>");
+        assert_eq!(highlight_match("Tom & Jerry", "x"), "Tom &amp; Jerry");
+    }
+}
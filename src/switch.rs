@@ -0,0 +1,111 @@
+use vgtk::lib::gtk::prelude::*;
+use vgtk::lib::gtk::Switch as GtkSwitch;
+use vgtk::lib::gtk::*;
+use vgtk::{gtk, gtk_if, Callback, Component, UpdateAction, VNode};
+
+use crate::labeled_field::help_view;
+
+#[derive(Clone, Debug)]
+pub struct Switch {
+    pub label: Option<String>,
+    pub active: bool,
+    // Same as `CheckBox::controlled`, the parent owns `active`
+    pub controlled: bool,
+    pub spacing: i32,
+    pub widget_name: String,
+    pub label_widget: String,
+    // Dimmed line under the box
+    pub help_text: Option<String>,
+    pub help_widget: String,
+    pub tooltip: Option<String>,
+    pub sensitive: bool,
+    // Same as `CheckBox::read_only`
+    pub read_only: bool,
+    pub on_toggled: Callback<bool>,
+    // Last value given by the parent, used to spot changes in uncontrolled mode
+    prop_active: bool,
+}
+
+impl Default for Switch {
+    fn default() -> Self {
+        Self {
+            label: None,
+            active: false,
+            controlled: false,
+            spacing: 10,
+            widget_name: String::new(),
+            label_widget: String::new(),
+            help_text: None,
+            help_widget: String::from("help"),
+            tooltip: None,
+            sensitive: true,
+            read_only: false,
+            on_toggled: Callback::default(),
+            prop_active: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum SwitchMessage {
+    Toggle { active: bool },
+}
+
+impl Component for Switch {
+    type Message = SwitchMessage;
+    type Properties = Self;
+
+    fn create(props: Self::Properties) -> Self {
+        let mut component = props;
+        component.prop_active = component.active;
+        component
+    }
+
+    fn change(&mut self, props: Self::Properties) -> UpdateAction<Self> {
+        let active = self.active;
+        let prop_active = self.prop_active;
+
+        *self = props;
+        self.prop_active = self.active;
+
+        if !self.controlled && self.active == prop_active {
+            self.active = active;
+        }
+
+        UpdateAction::Render
+    }
+
+    fn update(&mut self, msg: Self::Message) -> UpdateAction<Self> {
+        match msg {
+            // Rendering `active` notifies as well, only react to the user flipping the switch
+            SwitchMessage::Toggle { active } if active != self.active && self.read_only => UpdateAction::Render,
+            SwitchMessage::Toggle { active } if active != self.active => {
+                if !self.controlled {
+                    self.active = active;
+                }
+                self.on_toggled.send(active);
+                UpdateAction::Render
+            }
+            SwitchMessage::Toggle { .. } => UpdateAction::None,
+        }
+    }
+
+    fn view(&self) -> VNode<Self> {
+        gtk! {
+            <Box orientation=Orientation::Vertical spacing=4 sensitive=self.sensitive
+                property_tooltip_text=self.tooltip.clone()>
+                <Box orientation=Orientation::Horizontal spacing=self.spacing>
+                    <GtkSwitch active=self.active widget_name=self.widget_name.clone() valign=Align::Center
+                        on property_active_notify=|switch| SwitchMessage::Toggle { active: switch.get_active() } />
+                    {
+                        gtk_if!(self.label.is_some() => {
+                            <Label label=self.label.as_ref().unwrap().to_string() widget_name=self.label_widget.clone()
+                                selectable=self.read_only />
+                        })
+                    }
+                </Box>
+                { help_view(self.help_text.clone(), self.help_widget.clone()) }
+            </Box>
+        }
+    }
+}
@@ -1,6 +1,17 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::{Rc, Weak};
+use std::task::{Context, Poll, Waker};
+
+use vgtk::lib::glib::{self, Type, WeakRef};
 use vgtk::lib::gtk::prelude::*;
 use vgtk::lib::gtk::*;
-use vgtk::{gtk, gtk_if, Callback, Component, UpdateAction, VNode};
+use vgtk::{gtk, Callback, Component, UpdateAction, VNode};
+
+use crate::labeled_field::{read_only_view, FieldLayout, LabeledField};
+use crate::suggestions::{current_token, highlight_match, replace_token, SuggestionSource};
 
 #[derive(Clone, Debug)]
 pub struct TextBox {
@@ -11,7 +22,41 @@ pub struct TextBox {
     pub full_width: bool,
     pub widget_name: String,
     pub label_widget: String,
+    // An underscore in the label marks the Alt shortcut that focuses the input
+    pub mnemonic: bool,
+    pub help_text: Option<String>,
+    // Shown in the empty entry
+    pub placeholder: Option<String>,
+    pub tooltip: Option<String>,
+    pub required: bool,
+    // Shown under the input, e.g. from a form's validation
+    pub error: Option<String>,
+    pub sensitive: bool,
+    // Shows the text as a label instead of the entry
+    pub read_only: bool,
+    // Waits for typing to pause this long before sending `on_changed`, 0 sends every keystroke
+    pub debounce_ms: u32,
     pub on_changed: Callback<String>,
+    // Sent on Enter, and when the entry loses focus with text that wasn't committed yet
+    pub on_commit: Callback<String>,
+    // Offers completions for the typed text in a popup, queried after `debounce_ms`
+    pub suggestions: Option<Rc<dyn SuggestionSource>>,
+    pub min_suggestion_length: usize,
+    // Completes only the text after the last separator, e.g. ',' for a list of recipients or tags
+    pub suggestion_separator: Option<char>,
+    // Sent with the suggestion the user picked, after `on_changed` with the completed text
+    pub on_suggestion: Callback<String>,
+    // Text as typed so far, `text` stays what the parent gave
+    draft: String,
+    prop_text: String,
+    committed: String,
+    // A debounced `on_changed` is waiting to be sent
+    pending: bool,
+    // Bumped on every keystroke so only the last timeout sends `on_changed` and only the latest
+    // suggestions are shown
+    generation: usize,
+    // Shared with the completion's signal handler, kept across `change`
+    completion: Rc<CompletionState>,
 }
 
 impl Default for TextBox {
@@ -24,7 +69,136 @@ impl Default for TextBox {
             full_width: false,
             widget_name: String::new(),
             label_widget: String::new(),
+            mnemonic: false,
+            help_text: None,
+            placeholder: None,
+            tooltip: None,
+            required: false,
+            error: None,
+            sensitive: true,
+            read_only: false,
+            debounce_ms: 0,
             on_changed: Callback::default(),
+            on_commit: Callback::default(),
+            suggestions: None,
+            min_suggestion_length: 1,
+            suggestion_separator: None,
+            on_suggestion: Callback::default(),
+            draft: String::new(),
+            prop_text: String::new(),
+            committed: String::new(),
+            pending: false,
+            generation: 0,
+            completion: Rc::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct CompletionState {
+    entry: RefCell<Option<WeakRef<Entry>>>,
+    // Only attached to the entry once there are suggestions to show
+    completion: RefCell<Option<EntryCompletion>>,
+    separator: Cell<Option<char>>,
+    // Suggestions picked in the popup, waiting to be reported
+    accepted: RefCell<VecDeque<String>>,
+    waker: RefCell<Option<Waker>>,
+    listening: Cell<bool>,
+    // Entry text set by the last pick, it doesn't need suggestions
+    completed_text: RefCell<Option<String>>,
+}
+
+impl CompletionState {
+    fn attach(state: &Rc<Self>) {
+        let entry = match state.entry.borrow().as_ref().and_then(|entry| entry.upgrade()) {
+            Some(entry) => entry,
+            None => return,
+        };
+        // The entry is created again e.g. after being read-only
+        let attached = state.completion.borrow().as_ref().and_then(|completion| completion.get_entry());
+        if attached.as_ref() == Some(entry.upcast_ref::<Widget>()) {
+            return;
+        }
+
+        let completion = EntryCompletion::new();
+        completion.set_model(Some(&ListStore::new(&[Type::String, Type::String])));
+        completion.set_text_column(0);
+        // Show the highlighted markup instead of the plain text
+        for cell in completion.get_cells() {
+            completion.clear_attributes(&cell);
+            completion.add_attribute(&cell, "markup", 1);
+        }
+        // The source already picked the candidates
+        completion.set_match_func(|_, _, _| true);
+
+        let weak = Rc::downgrade(state);
+        completion.connect_match_selected(move |completion, model, iter| {
+            let state = match weak.upgrade() {
+                Some(state) => state,
+                None => return Inhibit(false),
+            };
+            let suggestion: String = model.get_value(iter, 0).get().ok().flatten().unwrap_or_default();
+            if let Some(entry) = completion.get_entry().and_then(|entry| entry.downcast::<Entry>().ok()) {
+                let text = replace_token(&entry.get_text(), &suggestion, state.separator.get());
+                state.completed_text.replace(Some(text.clone()));
+                entry.set_text(&text);
+                entry.set_position(-1);
+            }
+            state.accepted.borrow_mut().push_back(suggestion);
+            if let Some(waker) = state.waker.borrow_mut().take() {
+                waker.wake();
+            }
+            Inhibit(true)
+        });
+
+        entry.set_completion(Some(&completion));
+        state.completion.replace(Some(completion));
+    }
+
+    fn show(&self, typed: &str, suggestions: &[String]) {
+        if let Some(completion) = self.completion.borrow().as_ref() {
+            let model = ListStore::new(&[Type::String, Type::String]);
+            for suggestion in suggestions {
+                model.insert_with_values(None, &[0, 1], &[suggestion, &highlight_match(suggestion, typed)]);
+            }
+            completion.set_model(Some(&model));
+            if !suggestions.is_empty() {
+                completion.complete();
+            }
+        }
+    }
+}
+
+// Lets a pending `NextAccepted` finish once the text box is gone
+impl Drop for CompletionState {
+    fn drop(&mut self) {
+        if let Some(waker) = self.waker.get_mut().take() {
+            waker.wake();
+        }
+    }
+}
+
+// Resolves with the next suggestion picked in the popup, or with `None` once the state is
+// dropped. Only holds a weak reference so a waiting listener doesn't keep the state alive.
+struct NextAccepted {
+    state: Weak<CompletionState>,
+}
+
+impl Future for NextAccepted {
+    type Output = Option<String>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let state = match self.state.upgrade() {
+            Some(state) => state,
+            None => return Poll::Ready(None),
+        };
+        let suggestion = state.accepted.borrow_mut().pop_front();
+        match suggestion {
+            Some(suggestion) => Poll::Ready(Some(suggestion)),
+            None => {
+                *state.waker.borrow_mut() = Some(cx.waker().clone());
+                Poll::Pending
+            }
         }
     }
 }
@@ -32,6 +206,80 @@ impl Default for TextBox {
 #[derive(Clone, Debug)]
 pub enum TextBoxMessage {
     SetValue { value: String },
+    Debounced { generation: usize },
+    // Enter was pressed
+    Activate,
+    FocusOut,
+    Suggested { typed: String, suggestions: Vec<String>, generation: usize },
+    Accepted { suggestion: String },
+    // Sent by the entry's realize handler
+    #[doc(hidden)]
+    EntryRealized,
+    // Sent by a waiting listener once the text box is gone, see `NextAccepted`
+    #[doc(hidden)]
+    StoppedListening,
+    // Sent by the layout's realize handlers, see `LabeledField::realized`
+    #[doc(hidden)]
+    Realized,
+}
+
+impl TextBox {
+    // Sends a debounced `on_changed` right away, e.g. before committing
+    fn flush(&mut self) {
+        if self.pending {
+            self.pending = false;
+            self.generation += 1;
+            self.on_changed.send(self.draft.clone());
+        }
+    }
+
+    fn suggest(&self) -> UpdateAction<Self> {
+        let source = match &self.suggestions {
+            Some(source) => source,
+            None => return UpdateAction::None,
+        };
+        CompletionState::attach(&self.completion);
+        let typed = current_token(&self.draft, self.suggestion_separator).to_string();
+        let completed = self.completion.completed_text.borrow().as_ref() == Some(&self.draft);
+        if completed || typed.chars().count() < self.min_suggestion_length {
+            self.completion.show(&typed, &[]);
+            return UpdateAction::None;
+        }
+
+        let suggestions = source.suggest(&typed);
+        let generation = self.generation;
+        UpdateAction::defer(async move {
+            TextBoxMessage::Suggested {
+                typed,
+                suggestions: suggestions.await,
+                generation,
+            }
+        })
+    }
+
+    // Waits for picks from the popup, only entries with suggestions have one. Started once the
+    // entry is realized, or with the first suggestions when the source was passed later on.
+    fn listen(&self) -> UpdateAction<Self> {
+        let realized = self.completion.entry.borrow().is_some();
+        if self.suggestions.is_none() || !realized || self.completion.listening.replace(true) {
+            return UpdateAction::None;
+        }
+        let next = NextAccepted {
+            state: Rc::downgrade(&self.completion),
+        };
+        UpdateAction::defer(async move {
+            match next.await {
+                Some(suggestion) => TextBoxMessage::Accepted { suggestion },
+                None => TextBoxMessage::StoppedListening,
+            }
+        })
+    }
+
+    fn commit(&mut self) {
+        self.flush();
+        self.committed = self.draft.clone();
+        self.on_commit.send(self.draft.clone());
+    }
 }
 
 impl Component for TextBox {
@@ -39,52 +287,142 @@ impl Component for TextBox {
     type Properties = Self;
 
     fn create(props: Self::Properties) -> Self {
-        props
+        let mut component = props;
+        component.draft = component.text.clone();
+        component.prop_text = component.text.clone();
+        component.committed = component.text.clone();
+        component.completion.separator.set(component.suggestion_separator);
+        component
     }
 
     fn change(&mut self, props: Self::Properties) -> UpdateAction<Self> {
+        let draft = std::mem::take(&mut self.draft);
+        let prop_text = std::mem::take(&mut self.prop_text);
+        let committed = std::mem::take(&mut self.committed);
+        let (pending, generation) = (self.pending, self.generation);
+        let completion = self.completion.clone();
+
         *self = props;
+        self.generation = generation;
+        self.completion = completion;
+        self.completion.separator.set(self.suggestion_separator);
+
+        // Keep the draft while the parent passes the same text (e.g. lagging behind a debounced
+        // `on_changed`) or echoes what was typed, replace it when the parent sets a new text
+        if self.text == prop_text || self.text == draft {
+            self.draft = draft;
+            self.committed = committed;
+            self.pending = pending;
+        } else {
+            self.draft = self.text.clone();
+            self.committed = self.text.clone();
+        }
+        self.prop_text = self.text.clone();
+
         UpdateAction::Render
     }
 
     fn update(&mut self, msg: Self::Message) -> UpdateAction<Self> {
         match msg {
+            // Rendering the draft fires `changed` as well
+            TextBoxMessage::SetValue { value } if value == self.draft => UpdateAction::None,
             TextBoxMessage::SetValue { value } => {
-                self.on_changed.send(value);
+                self.draft = value;
+                self.generation += 1;
+                if self.debounce_ms == 0 {
+                    self.on_changed.send(self.draft.clone());
+                    return self.suggest();
+                }
+
+                self.pending = true;
+                let generation = self.generation;
+                let timeout = glib::timeout_future(self.debounce_ms);
+                UpdateAction::defer(async move {
+                    timeout.await;
+                    TextBoxMessage::Debounced { generation }
+                })
+            }
+            TextBoxMessage::Debounced { generation } => {
+                if generation != self.generation {
+                    return UpdateAction::None;
+                }
+                self.flush();
+                self.suggest()
+            }
+            TextBoxMessage::Activate => {
+                self.commit();
+                UpdateAction::None
+            }
+            TextBoxMessage::FocusOut => {
+                if self.draft != self.committed {
+                    self.commit();
+                }
                 UpdateAction::None
             }
+            TextBoxMessage::Suggested {
+                typed,
+                suggestions,
+                generation,
+            } => {
+                // Dropped when the text changed since they were asked for
+                if generation != self.generation {
+                    return UpdateAction::None;
+                }
+                self.completion.show(&typed, &suggestions);
+                self.listen()
+            }
+            TextBoxMessage::Accepted { suggestion } => {
+                self.flush();
+                self.on_suggestion.send(suggestion);
+                self.completion.listening.set(false);
+                self.listen()
+            }
+            TextBoxMessage::EntryRealized => self.listen(),
+            TextBoxMessage::StoppedListening | TextBoxMessage::Realized => UpdateAction::None,
         }
     }
 
     fn view(&self) -> VNode<Self> {
-        gtk! {
-            <Box orientation=self.orientation spacing=self.spacing>
-                {
-                    gtk_if!(self.label.is_some() => {
-                        <Label label=self.label.as_ref().unwrap().to_string() widget_name=self.label_widget.clone()
-                            halign=Align::Start />
-                    })
-                }
-                {
-                    if self.full_width {
-                        gtk! {
-                            <Entry text=self.text.clone() widget_name=self.widget_name.clone() Box::pack_type=PackType::End on changed=|entry| {
-                                TextBoxMessage::SetValue {
-                                    value: entry.get_text().to_string(),
-                                }
-                            } />
-                        }
-                    } else {
-                        gtk! {
-                            <Entry text=self.text.clone() widget_name=self.widget_name.clone() on changed=|entry| {
-                                TextBoxMessage::SetValue {
-                                    value: entry.get_text().to_string(),
-                                }
-                            } />
-                        }
-                    }
+        if self.read_only {
+            return self.labeled(read_only_view(self.draft.clone(), self.widget_name.clone()));
+        }
+        let completion = self.completion.clone();
+        self.labeled(gtk! {
+            <Entry text=self.draft.clone() widget_name=self.widget_name.clone()
+             property_placeholder_text=self.placeholder.clone() on changed=|entry| {
+                TextBoxMessage::SetValue {
+                    value: entry.get_text().to_string(),
                 }
-            </Box>
+            }
+             on realize=|entry| {
+                completion.entry.replace(Some(entry.downgrade()));
+                TextBoxMessage::EntryRealized
+            }
+             on activate=|_| TextBoxMessage::Activate
+             on focus_out_event=|_, _| TextBoxMessage::FocusOut />
+        })
+    }
+}
+
+impl LabeledField for TextBox {
+    fn layout(&self) -> FieldLayout {
+        FieldLayout {
+            label: self.label.clone(),
+            orientation: self.orientation,
+            spacing: self.spacing,
+            full_width: self.full_width,
+            label_widget: self.label_widget.clone(),
+            mnemonic: self.mnemonic,
+            help_text: self.help_text.clone(),
+            required: self.required,
+            error: self.error.clone(),
+            tooltip: self.tooltip.clone(),
+            sensitive: self.sensitive,
+            ..FieldLayout::default()
         }
     }
+
+    fn realized() -> Self::Message {
+        TextBoxMessage::Realized
+    }
 }
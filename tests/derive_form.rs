@@ -0,0 +1,142 @@
+use chrono::NaiveDate;
+
+use vgtk_components::date::Date;
+use vgtk_components::form::{FieldKind, FieldValue, FormValues};
+use vgtk_components::struct_form::{FormChoice, VgtkForm};
+
+#[derive(Clone, Copy, Debug, PartialEq, FormChoice)]
+enum Size {
+    Small,
+    #[form(label = "Extra large")]
+    ExtraLarge,
+}
+
+impl Default for Size {
+    fn default() -> Self {
+        Size::Small
+    }
+}
+
+fn not_empty(value: &FieldValue) -> Result<(), String> {
+    match value {
+        FieldValue::Text(text) if text.is_empty() => Err(String::from("Empty")),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, VgtkForm)]
+struct Order {
+    #[form(required, validate = "not_empty", help = "As on the card", placeholder = "Jane Doe", tooltip = "Full name")]
+    full_name: String,
+    #[form(password)]
+    pin: String,
+    #[form(order = 1)]
+    size: Size,
+    #[form(switch, label = "Gift wrap")]
+    gift: bool,
+    express: bool,
+    delivery: NaiveDate,
+    #[form(skip)]
+    internal_id: u32,
+}
+
+impl Default for Order {
+    fn default() -> Self {
+        Self {
+            full_name: String::from("Ann"),
+            pin: String::new(),
+            size: Size::ExtraLarge,
+            gift: true,
+            express: false,
+            delivery: NaiveDate::from_ymd(2020, 5, 17),
+            internal_id: 7,
+        }
+    }
+}
+
+#[test]
+fn form_choice_lists_variants() {
+    assert_eq!(Size::choices(), vec![String::from("Small"), String::from("Extra large")]);
+    assert_eq!(Size::ExtraLarge.to_choice(), "Extra large");
+    assert_eq!(Size::from_choice("Small"), Some(Size::Small));
+    assert_eq!(Size::from_choice("Medium"), None);
+}
+
+#[test]
+fn form_fields_follow_the_struct() {
+    let fields = Order::default().form_fields();
+
+    let keys: Vec<&str> = fields.iter().map(|field| field.key.as_str()).collect();
+    // `order` moves `size` after the fields without one, `skip` leaves `internal_id` out
+    assert_eq!(keys, vec!["full_name", "pin", "gift", "express", "delivery", "size"]);
+
+    let labels: Vec<&str> = fields.iter().map(|field| field.label.as_str()).collect();
+    assert_eq!(labels, vec!["Full name:", "Pin:", "Gift wrap", "Express", "Delivery:", "Size:"]);
+
+    let kinds: Vec<FieldKind> = fields.iter().map(|field| field.kind.clone()).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            FieldKind::Text,
+            FieldKind::Password,
+            FieldKind::Switch,
+            FieldKind::CheckBox,
+            FieldKind::Date,
+            FieldKind::Dropdown { items: Size::choices() },
+        ]
+    );
+
+    let name = &fields[0];
+    assert!(name.required);
+    assert_eq!(name.validators.len(), 1);
+    assert_eq!(name.help_text.as_deref(), Some("As on the card"));
+    assert_eq!(name.placeholder.as_deref(), Some("Jane Doe"));
+    assert_eq!(name.tooltip.as_deref(), Some("Full name"));
+    assert_eq!(name.initial, FieldValue::Text(String::from("Ann")));
+    assert_eq!(fields[5].initial, FieldValue::Choice(Some(String::from("Extra large"))));
+}
+
+#[test]
+fn form_values_apply_to_the_struct() {
+    let mut values = FormValues::default();
+    values.set("full_name", FieldValue::Text(String::from("Bob")));
+    values.set("gift", FieldValue::Bool(false));
+    values.set("express", FieldValue::Bool(true));
+    values.set("size", FieldValue::Choice(Some(String::from("Small"))));
+    values.set("delivery", FieldValue::Date(Date::from(NaiveDate::from_ymd(2021, 2, 3))));
+
+    let mut order = Order::default();
+    order.apply_form_values(&values);
+
+    assert_eq!(
+        order,
+        Order {
+            full_name: String::from("Bob"),
+            gift: false,
+            express: true,
+            size: Size::Small,
+            delivery: NaiveDate::from_ymd(2021, 2, 3),
+            ..Order::default()
+        }
+    );
+}
+
+#[test]
+fn unconvertible_values_are_ignored() {
+    let mut values = FormValues::default();
+    values.set("size", FieldValue::Choice(Some(String::from("Medium"))));
+    values.set("delivery", FieldValue::Date(Date::default()));
+
+    let mut order = Order::default();
+    order.apply_form_values(&values);
+
+    assert_eq!(order, Order::default());
+    assert_eq!(order.internal_id, 7);
+}
+
+#[test]
+fn alias_edits_the_struct() {
+    let form = OrderForm::default();
+    assert_eq!(form.value, Order::default());
+    assert_eq!(form.submit_label, "Save");
+}
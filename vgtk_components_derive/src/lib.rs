@@ -0,0 +1,289 @@
+//! Derive macros for `vgtk_components`, re-exported from its `struct_form` module.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, Ident, Lit, Meta, NestedMeta, Path, Type,
+};
+
+/// Implements `VgtkForm` for a struct with named fields and defines `<Struct>Form`, a
+/// `StructForm` component editing it.
+///
+/// Fields map to components by type: `String` to `TextBox`, `bool` to `CheckBox`, `NaiveDate` to
+/// `DateInput`, and anything else to a `Dropdown`, which needs the type to implement
+/// `FormChoice`. Fields take `#[form(...)]` options:
+///
+/// - `label = "..."`: defaults to the field name in sentence case
+/// - `order = n`: fields are sorted by it, declaration order breaks ties, the default is 0
+/// - `required`
+/// - `help = "..."`: dimmed text under the field
+/// - `placeholder = "..."`: text shown in an empty `String` field
+/// - `tooltip = "..."`: shown when hovering the field
+/// - `validate = "path::to::fn"`: a `fn(&FieldValue) -> Result<(), String>`, can be repeated
+/// - `password` or `phone` for `String`s, `switch` for `bool`s
+/// - `skip`: leaves the field out of the form
+#[proc_macro_derive(VgtkForm, attributes(form))]
+pub fn derive_vgtk_form(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    vgtk_form(input).unwrap_or_else(|error| error.to_compile_error()).into()
+}
+
+/// Implements `FormChoice` for an enum of unit variants. Variants are offered under their name,
+/// or under `#[form(label = "...")]`.
+#[proc_macro_derive(FormChoice, attributes(form))]
+pub fn derive_form_choice(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    form_choice(input).unwrap_or_else(|error| error.to_compile_error()).into()
+}
+
+enum Kind {
+    // Holds the `FormField` constructor to use
+    Text(Ident),
+    Bool(Ident),
+    Date,
+    Choice,
+}
+
+#[derive(Default)]
+struct FieldOptions {
+    label: Option<String>,
+    order: i64,
+    required: bool,
+    skip: bool,
+    widget: Option<Ident>,
+    validators: Vec<Path>,
+    help: Option<String>,
+    placeholder: Option<String>,
+    tooltip: Option<String>,
+}
+
+fn field_options(attrs: &[Attribute]) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("form")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(Error::new_spanned(meta, "expected `#[form(...)]`")),
+        };
+        for nested in list.nested {
+            match &nested {
+                NestedMeta::Meta(Meta::Path(path)) => match path.get_ident() {
+                    Some(ident) if ident == "required" => options.required = true,
+                    Some(ident) if ident == "skip" => options.skip = true,
+                    Some(ident) if ident == "password" || ident == "phone" || ident == "switch" => {
+                        options.widget = Some(ident.clone())
+                    }
+                    _ => return Err(Error::new_spanned(nested, "unknown form option")),
+                },
+                NestedMeta::Meta(Meta::NameValue(name_value)) => match (name_value.path.get_ident(), &name_value.lit) {
+                    (Some(ident), Lit::Str(label)) if ident == "label" => options.label = Some(label.value()),
+                    (Some(ident), Lit::Str(path)) if ident == "validate" => options.validators.push(path.parse()?),
+                    (Some(ident), Lit::Str(help)) if ident == "help" => options.help = Some(help.value()),
+                    (Some(ident), Lit::Str(placeholder)) if ident == "placeholder" => {
+                        options.placeholder = Some(placeholder.value())
+                    }
+                    (Some(ident), Lit::Str(tooltip)) if ident == "tooltip" => options.tooltip = Some(tooltip.value()),
+                    (Some(ident), Lit::Int(order)) if ident == "order" => options.order = order.base10_parse()?,
+                    _ => return Err(Error::new_spanned(nested, "unknown form option")),
+                },
+                _ => return Err(Error::new_spanned(nested, "unknown form option")),
+            }
+        }
+    }
+    Ok(options)
+}
+
+fn type_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(path) => path.path.segments.last().map(|segment| segment.ident.to_string()),
+        _ => None,
+    }
+}
+
+fn field_kind(ty: &Type, widget: Option<&Ident>) -> syn::Result<Kind> {
+    let name = type_name(ty);
+    let kind = match (name.as_deref(), widget) {
+        (Some("String"), None) => Kind::Text(Ident::new("text", Span::call_site())),
+        (Some("String"), Some(widget)) if widget != "switch" => Kind::Text(widget.clone()),
+        (Some("bool"), None) => Kind::Bool(Ident::new("checkbox", Span::call_site())),
+        (Some("bool"), Some(widget)) if widget == "switch" => Kind::Bool(widget.clone()),
+        (Some("NaiveDate"), None) => Kind::Date,
+        (_, None) => Kind::Choice,
+        (_, Some(widget)) => return Err(Error::new_spanned(widget, "option doesn't fit the field's type")),
+    };
+    Ok(kind)
+}
+
+// `display_name` becomes "Display name"
+fn default_label(ident: &Ident) -> String {
+    let name = ident.to_string().trim_start_matches("r#").replace('_', " ");
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => name,
+    }
+}
+
+fn vgtk_form(input: DeriveInput) -> syn::Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(input.generics, "VgtkForm can't be derived for generic structs"));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new_spanned(&input.ident, "VgtkForm needs named fields")),
+        },
+        _ => return Err(Error::new_spanned(&input.ident, "VgtkForm can only be derived for structs")),
+    };
+
+    let krate = quote!(::vgtk_components);
+    let mut form_fields = vec![];
+    let mut applies = vec![];
+    for field in fields {
+        let options = field_options(&field.attrs)?;
+        if options.skip {
+            continue;
+        }
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        let key = ident.to_string();
+        let kind = field_kind(ty, options.widget.as_ref())?;
+        let label = options.label.unwrap_or_else(|| match kind {
+            // Check boxes and switches have their label next to them
+            Kind::Bool(_) => default_label(ident),
+            _ => format!("{}:", default_label(ident)),
+        });
+
+        let (mut form_field, apply) = match &kind {
+            Kind::Text(constructor) => (
+                quote! {
+                    #krate::form::FormField::#constructor(#key, #label)
+                        .initial(#krate::form::FieldValue::Text(self.#ident.clone()))
+                },
+                quote! {
+                    if let Some(value) = values.text(#key) {
+                        self.#ident = value.to_string();
+                    }
+                },
+            ),
+            Kind::Bool(constructor) => (
+                quote! {
+                    #krate::form::FormField::#constructor(#key, #label)
+                        .initial(#krate::form::FieldValue::Bool(self.#ident))
+                },
+                quote! {
+                    if let Some(value) = values.bool(#key) {
+                        self.#ident = value;
+                    }
+                },
+            ),
+            Kind::Date => (
+                quote! {
+                    #krate::form::FormField::date(#key, #label)
+                        .initial(#krate::form::FieldValue::Date(#krate::date::Date::from(self.#ident)))
+                },
+                quote! {
+                    if let Some(value) = values.date(#key).and_then(#krate::date::Date::to_naive_date) {
+                        self.#ident = value;
+                    }
+                },
+            ),
+            Kind::Choice => (
+                quote! {
+                    #krate::form::FormField::dropdown(#key, #label, <#ty as #krate::struct_form::FormChoice>::choices())
+                        .initial(#krate::form::FieldValue::Choice(Some(
+                            #krate::struct_form::FormChoice::to_choice(&self.#ident),
+                        )))
+                },
+                quote! {
+                    if let Some(value) = values.choice(#key).and_then(<#ty as #krate::struct_form::FormChoice>::from_choice) {
+                        self.#ident = value;
+                    }
+                },
+            ),
+        };
+        if options.required {
+            form_field = quote!(#form_field.required());
+        }
+        for validator in &options.validators {
+            form_field = quote!(#form_field.validate(#validator));
+        }
+        if let Some(help) = &options.help {
+            form_field = quote!(#form_field.help(#help));
+        }
+        if let Some(placeholder) = &options.placeholder {
+            form_field = quote!(#form_field.placeholder(#placeholder));
+        }
+        if let Some(tooltip) = &options.tooltip {
+            form_field = quote!(#form_field.tooltip(#tooltip));
+        }
+
+        form_fields.push((options.order, form_field));
+        applies.push(apply);
+    }
+    // A stable sort, so declaration order breaks ties
+    form_fields.sort_by_key(|(order, _)| *order);
+    let form_fields = form_fields.into_iter().map(|(_, form_field)| form_field);
+
+    let name = &input.ident;
+    let vis = &input.vis;
+    let alias = format_ident!("{}Form", name);
+    let alias_doc = format!("Edits a `{}`.", name);
+    Ok(quote! {
+        impl #krate::struct_form::VgtkForm for #name {
+            fn form_fields(&self) -> Vec<#krate::form::FormField> {
+                vec![#(#form_fields),*]
+            }
+
+            fn apply_form_values(&mut self, values: &#krate::form::FormValues) {
+                #(#applies)*
+            }
+        }
+
+        #[doc = #alias_doc]
+        #vis type #alias = #krate::struct_form::StructForm<#name>;
+    })
+}
+
+fn form_choice(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let variants = match &input.data {
+        Data::Enum(data) if !data.variants.is_empty() => &data.variants,
+        _ => return Err(Error::new_spanned(&input.ident, "FormChoice can only be derived for non-empty enums")),
+    };
+
+    let mut idents = vec![];
+    let mut labels = vec![];
+    for variant in variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(variant, "FormChoice variants can't have fields"));
+        }
+        let options = field_options(&variant.attrs)?;
+        labels.push(options.label.unwrap_or_else(|| variant.ident.to_string()));
+        idents.push(&variant.ident);
+    }
+
+    let name = &input.ident;
+    let krate = quote!(::vgtk_components);
+    Ok(quote! {
+        impl #krate::struct_form::FormChoice for #name {
+            fn choices() -> Vec<String> {
+                vec![#(String::from(#labels)),*]
+            }
+
+            fn to_choice(&self) -> String {
+                match self {
+                    #(#name::#idents => String::from(#labels),)*
+                }
+            }
+
+            fn from_choice(choice: &str) -> Option<Self> {
+                match choice {
+                    #(#labels => Some(#name::#idents),)*
+                    _ => None,
+                }
+            }
+        }
+    })
+}